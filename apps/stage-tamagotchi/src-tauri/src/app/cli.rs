@@ -0,0 +1,38 @@
+use clap::{Parser, error::ErrorKind};
+
+/// Command line arguments of the desktop app.
+#[derive(Debug, Default, Parser)]
+#[command(version, about)]
+pub struct Cli {
+  /// Serve AIRI as an MCP server over stdin / stdout, for agents that launch
+  /// the app themselves. The app quits when stdin is closed.
+  #[arg(long)]
+  pub mcp_stdio: bool,
+
+  /// Serve AIRI as an MCP server over Streamable HTTP on
+  /// `http://127.0.0.1:<PORT>/mcp`.
+  #[arg(long, value_name = "PORT")]
+  pub mcp_http: Option<u16>,
+}
+
+impl Cli {
+  /// Parse the arguments of the process without exiting on unknown ones,
+  /// which the OS or an updater may pass (e.g. `-psn_*` on macOS, deep links
+  /// or mobile launch arguments). Returns the defaults and the error then,
+  /// to be logged once logging is set up. `--help` and `--version` still
+  /// print and exit.
+  pub fn parse_lenient() -> (Self, Option<clap::Error>) {
+    match Self::try_parse() {
+      Ok(cli) => (cli, None),
+      Err(err)
+        if matches!(
+          err.kind(),
+          ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
+        ) =>
+      {
+        err.exit()
+      },
+      Err(err) => (Self::default(), Some(err)),
+    }
+  }
+}
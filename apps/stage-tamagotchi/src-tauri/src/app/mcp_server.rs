@@ -0,0 +1,459 @@
+use std::{
+  fs::OpenOptions,
+  io::Write,
+  net::Ipv4Addr,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+use axum::{
+  extract::{Request, State},
+  http::{StatusCode, header},
+  middleware::{self, Next},
+  response::{IntoResponse, Response},
+};
+use log::{error, info, warn};
+use rmcp::{
+  ErrorData,
+  ServerHandler,
+  ServiceExt,
+  handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+  model::{CallToolResult, Content, Implementation, ServerCapabilities, ServerInfo},
+  schemars,
+  tool,
+  tool_handler,
+  tool_router,
+  transport::{
+    StreamableHttpServerConfig,
+    StreamableHttpService,
+    stdio,
+    streamable_http_server::session::local::LocalSessionManager,
+  },
+};
+use serde::Deserialize;
+use tauri::{AppHandle, Emitter, LogicalPosition, Manager};
+use tauri_plugin_positioner::{Position, WindowExt};
+use tauri_plugin_window_state::{AppHandleExt, StateFlags};
+
+use crate::app::{cli::Cli, commands};
+
+/// Event the main window speaks the payload of.
+pub const EVENT_SAY: &str = "tauri-main:main:say";
+
+/// File in the app data dir the bearer token of the HTTP server is written
+/// to, agents read it from there.
+pub const TOKEN_FILE_NAME: &str = "mcp-server-token";
+
+/// Extensions of the files `transcribe_audio` accepts.
+const AUDIO_EXTENSIONS: &[&str] = &["wav", "flac", "ogg", "oga"];
+
+/// Hosts the HTTP server answers to, anything else may be a DNS rebinding
+/// attack from a web page.
+const ALLOWED_HOSTS: &[&str] = &["localhost", "127.0.0.1"];
+
+#[derive(Debug, Clone, Copy, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowPosition {
+  Center,
+  TopLeft,
+  TopRight,
+  BottomLeft,
+  BottomRight,
+}
+
+impl From<WindowPosition> for Position {
+  fn from(position: WindowPosition) -> Self {
+    match position {
+      WindowPosition::Center => Position::Center,
+      WindowPosition::TopLeft => Position::TopLeft,
+      WindowPosition::TopRight => Position::TopRight,
+      WindowPosition::BottomLeft => Position::BottomLeft,
+      WindowPosition::BottomRight => Position::BottomRight,
+    }
+  }
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct MoveWindowRequest {
+  #[schemars(description = "Snap the window to a corner or the center of the screen")]
+  pub position: Option<WindowPosition>,
+  #[schemars(
+    description = "Horizontal position in logical pixels, used when `position` is not set"
+  )]
+  pub x:        Option<f64>,
+  #[schemars(description = "Vertical position in logical pixels, used when `position` is not set")]
+  pub y:        Option<f64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct TranscribeRequest {
+  #[schemars(description = "Path of the audio file, e.g. WAV, FLAC or OGG")]
+  pub path:     PathBuf,
+  #[schemars(description = "Language spoken in the file, detected when omitted")]
+  pub language: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SayRequest {
+  #[schemars(description = "Text for AIRI to speak with the configured voice")]
+  pub text: String,
+}
+
+/// AIRI's own capabilities, served to external agents over MCP.
+#[derive(Clone)]
+pub struct AiriMcpServer {
+  app:         AppHandle,
+  tool_router: ToolRouter<Self>,
+}
+
+fn text(message: impl Into<String>) -> Result<CallToolResult, ErrorData> {
+  Ok(CallToolResult::success(vec![Content::text(message)]))
+}
+
+fn failure(message: impl ToString) -> Result<CallToolResult, ErrorData> {
+  Ok(CallToolResult::error(vec![Content::text(
+    message.to_string(),
+  )]))
+}
+
+#[tool_router]
+impl AiriMcpServer {
+  pub fn new(app: AppHandle) -> Self {
+    Self {
+      app,
+      tool_router: Self::tool_router(),
+    }
+  }
+
+  fn main_window(&self) -> Result<tauri::WebviewWindow, String> {
+    self
+      .app
+      .get_webview_window("main")
+      .ok_or_else(|| "Main window is not open".to_string())
+  }
+
+  #[tool(description = "Open the chat window")]
+  async fn open_chat_window(&self) -> Result<CallToolResult, ErrorData> {
+    match commands::open_chat_window(self.app.clone()).await {
+      Ok(()) => text("Chat window opened"),
+      Err(err) => failure(err),
+    }
+  }
+
+  #[tool(description = "Open the settings window")]
+  async fn open_settings_window(&self) -> Result<CallToolResult, ErrorData> {
+    match commands::open_settings_window(self.app.clone()).await {
+      Ok(()) => text("Settings window opened"),
+      Err(err) => failure(err),
+    }
+  }
+
+  #[tool(description = "Move the main window with the character")]
+  async fn move_window(
+    &self,
+    Parameters(request): Parameters<MoveWindowRequest>,
+  ) -> Result<CallToolResult, ErrorData> {
+    let window = match self.main_window() {
+      Ok(window) => window,
+      Err(err) => return failure(err),
+    };
+
+    let moved = match request {
+      MoveWindowRequest {
+        position: Some(position),
+        ..
+      } => window.move_window(position.into()),
+      MoveWindowRequest {
+        x: Some(x),
+        y: Some(y),
+        ..
+      } => window.set_position(LogicalPosition::new(x, y)),
+      _ => return failure("Either `position` or both `x` and `y` must be set"),
+    };
+
+    match moved {
+      Ok(()) => {
+        let _ = self.app.save_window_state(StateFlags::POSITION);
+        text("Window moved")
+      },
+      Err(err) => failure(err),
+    }
+  }
+
+  #[tool(description = "Hide the main window with the character")]
+  async fn hide_window(&self) -> Result<CallToolResult, ErrorData> {
+    match self
+      .main_window()
+      .and_then(|window| window.hide().map_err(|e| e.to_string()))
+    {
+      Ok(()) => text("Window hidden"),
+      Err(err) => failure(err),
+    }
+  }
+
+  #[tool(description = "Show the main window with the character again")]
+  async fn show_window(&self) -> Result<CallToolResult, ErrorData> {
+    match self
+      .main_window()
+      .and_then(|window| window.show().map_err(|e| e.to_string()))
+    {
+      Ok(()) => text("Window shown"),
+      Err(err) => failure(err),
+    }
+  }
+
+  #[tool(description = "Transcribe an audio file to text with the local Whisper model")]
+  async fn transcribe_audio(
+    &self,
+    Parameters(request): Parameters<TranscribeRequest>,
+  ) -> Result<CallToolResult, ErrorData> {
+    if let Err(err) = check_audio_file(&request.path) {
+      return failure(err);
+    }
+
+    let app = self.app.clone();
+    let transcription = tauri::async_runtime::spawn_blocking(move || {
+      tauri_plugin_ipc_audio_transcription_ort::transcribe_file(
+        &app,
+        &request.path,
+        request.language,
+      )
+    })
+    .await;
+
+    match transcription {
+      Ok(Ok(transcription)) => text(transcription),
+      Ok(Err(err)) => failure(err),
+      Err(err) => failure(err),
+    }
+  }
+
+  #[tool(description = "Make AIRI say something out loud")]
+  async fn say(
+    &self,
+    Parameters(request): Parameters<SayRequest>,
+  ) -> Result<CallToolResult, ErrorData> {
+    let emitted = self.main_window().and_then(|window| {
+      window
+        .emit(EVENT_SAY, request.text)
+        .map_err(|e| e.to_string())
+    });
+
+    match emitted {
+      Ok(()) => text("Speaking"),
+      Err(err) => failure(err),
+    }
+  }
+}
+
+#[tool_handler]
+impl ServerHandler for AiriMcpServer {
+  fn get_info(&self) -> ServerInfo {
+    ServerInfo {
+      capabilities: ServerCapabilities::builder()
+        .enable_tools()
+        .build(),
+      server_info: Implementation {
+        name: "airi".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        ..Default::default()
+      },
+      instructions: Some(
+        "Controls AIRI, a desktop character: her windows, her voice and speech recognition."
+          .to_string(),
+      ),
+      ..Default::default()
+    }
+  }
+}
+
+/// Only let the agent read audio files, not arbitrary files of the user.
+fn check_audio_file(path: &Path) -> Result<(), String> {
+  let is_audio = path
+    .extension()
+    .and_then(|extension| extension.to_str())
+    .is_some_and(|extension| {
+      AUDIO_EXTENSIONS
+        .iter()
+        .any(|allowed| extension.eq_ignore_ascii_case(allowed))
+    });
+  if !is_audio {
+    return Err(format!(
+      "Only audio files ({}) can be transcribed",
+      AUDIO_EXTENSIONS.join(", ")
+    ));
+  }
+  if !path.is_file() {
+    return Err(format!("{} is not a file", path.display()));
+  }
+
+  Ok(())
+}
+
+/// Whether `host`, a `Host` header or the host of an `Origin`, points at
+/// this machine.
+fn is_allowed_host(host: &str) -> bool {
+  let host = match host.rsplit_once(':') {
+    Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+    _ => host,
+  };
+  ALLOWED_HOSTS
+    .iter()
+    .any(|allowed| host.eq_ignore_ascii_case(allowed))
+}
+
+fn is_allowed_origin(origin: &str) -> bool {
+  url::Url::parse(origin).is_ok_and(|origin| {
+    matches!(origin.scheme(), "http" | "https") && origin.host_str().is_some_and(is_allowed_host)
+  })
+}
+
+/// Compare without bailing out at the first differing byte, so the token
+/// can't be guessed from response times.
+fn constant_time_eq(
+  a: &[u8],
+  b: &[u8],
+) -> bool {
+  a.len() == b.len()
+    && a
+      .iter()
+      .zip(b)
+      .fold(0, |acc, (a, b)| acc | (a ^ b))
+      == 0
+}
+
+/// Reject requests not addressed to localhost, sent from a foreign web page
+/// or without the bearer token.
+async fn guard(
+  State(token): State<Arc<String>>,
+  request: Request,
+  next: Next,
+) -> Response {
+  let headers = request.headers();
+
+  let host = headers
+    .get(header::HOST)
+    .and_then(|host| host.to_str().ok());
+  if !host.is_some_and(is_allowed_host) {
+    warn!("Rejected MCP request for host {host:?}");
+    return (StatusCode::FORBIDDEN, "Forbidden host").into_response();
+  }
+
+  if let Some(origin) = headers.get(header::ORIGIN) {
+    if !origin.to_str().is_ok_and(is_allowed_origin) {
+      warn!("Rejected MCP request from origin {origin:?}");
+      return (StatusCode::FORBIDDEN, "Forbidden origin").into_response();
+    }
+  }
+
+  let authorized = headers
+    .get(header::AUTHORIZATION)
+    .and_then(|authorization| authorization.to_str().ok())
+    .and_then(|authorization| authorization.strip_prefix("Bearer "))
+    .is_some_and(|bearer| constant_time_eq(bearer.trim().as_bytes(), token.as_bytes()));
+  if !authorized {
+    return (
+      StatusCode::UNAUTHORIZED,
+      [(header::WWW_AUTHENTICATE, "Bearer")],
+      "Missing or invalid bearer token",
+    )
+      .into_response();
+  }
+
+  next.run(request).await
+}
+
+/// Generate the bearer token of this run and write it to the app data dir,
+/// readable only by the current user.
+fn create_token(app: &AppHandle) -> anyhow::Result<(String, PathBuf)> {
+  let mut bytes = [0u8; 32];
+  getrandom::fill(&mut bytes)
+    .map_err(|err| anyhow::anyhow!("Failed to generate a token: {err}"))?;
+  let token = bytes
+    .iter()
+    .map(|byte| format!("{byte:02x}"))
+    .collect::<String>();
+
+  let dir = app.path().app_data_dir()?;
+  std::fs::create_dir_all(&dir)?;
+  let path = dir.join(TOKEN_FILE_NAME);
+
+  let mut options = OpenOptions::new();
+  options.create(true).write(true).truncate(true);
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+  }
+  options.open(&path)?.write_all(token.as_bytes())?;
+
+  Ok((token, path))
+}
+
+/// Start the MCP server transports requested on the command line.
+pub fn start(
+  app: &AppHandle,
+  cli: &Cli,
+) {
+  if cli.mcp_stdio {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+      info!("Serving MCP over stdio");
+      match AiriMcpServer::new(app.clone())
+        .serve(stdio())
+        .await
+      {
+        Ok(service) => {
+          if let Err(err) = service.waiting().await {
+            error!("MCP stdio server failed: {err}");
+          }
+        },
+        Err(err) => error!("Failed to serve MCP over stdio: {err}"),
+      }
+
+      // The agent that launched us is gone
+      info!("MCP stdio client disconnected, exiting");
+      app.exit(0);
+    });
+  }
+
+  if let Some(port) = cli.mcp_http {
+    let (token, token_path) = match create_token(app) {
+      Ok(token) => token,
+      Err(err) => {
+        error!("Failed to create the MCP server token, not serving MCP over HTTP: {err}");
+        return;
+      },
+    };
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+      let service: StreamableHttpService<AiriMcpServer, LocalSessionManager> =
+        StreamableHttpService::new(
+          move || Ok(AiriMcpServer::new(app.clone())),
+          Default::default(),
+          StreamableHttpServerConfig::default(),
+        );
+      let router = axum::Router::new()
+        .nest_service("/mcp", service)
+        .layer(middleware::from_fn_with_state(Arc::new(token), guard));
+
+      // Only reachable from this machine, and only with the token
+      let listener = match tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await {
+        Ok(listener) => listener,
+        Err(err) => {
+          error!("Failed to listen on port {port} for MCP: {err}");
+          return;
+        },
+      };
+
+      info!(
+        "Serving MCP on http://127.0.0.1:{port}/mcp, the bearer token is in {}",
+        token_path.display()
+      );
+      if let Err(err) = axum::serve(listener, router).await {
+        error!("MCP HTTP server failed: {err}");
+      }
+    });
+  }
+}
@@ -1,2 +1,4 @@
+pub mod cli;
 pub mod commands;
+pub mod mcp_server;
 pub mod windows;
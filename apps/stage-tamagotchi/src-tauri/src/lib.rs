@@ -6,6 +6,7 @@ use tauri::{
   menu::{Menu, MenuItem, Submenu},
   tray::TrayIconBuilder,
 };
+use tauri_plugin_log::{Target, TargetKind};
 use tauri_plugin_positioner::WindowExt;
 use tauri_plugin_prevent_default::Flags;
 use tauri_plugin_window_router_link::WindowMatcher;
@@ -13,10 +14,16 @@ use tauri_plugin_window_state::{AppHandleExt, StateFlags};
 
 mod app;
 
-use app::windows::{chat, onboarding, settings};
+use app::{
+  cli::Cli,
+  mcp_server,
+  windows::{chat, onboarding, settings},
+};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+  let (cli, cli_error) = Cli::parse_lenient();
+
   let prevent_default_plugin = tauri_plugin_prevent_default::Builder::new()
     .with_flags(Flags::RELOAD)
     .build();
@@ -49,7 +56,7 @@ pub fn run() {
             .map_err(|e| e)
         })
     ))
-    .setup(|app| {
+    .setup(move |app| {
       let mut builder = WebviewWindowBuilder::new(app, "main", WebviewUrl::default());
 
       builder = builder.title("AIRI")
@@ -74,12 +81,22 @@ pub fn run() {
       }
 
       if cfg!(debug_assertions) {
-        app.handle().plugin(
-          tauri_plugin_log::Builder::default()
-            .level(log::LevelFilter::Info)
-            .build(),
-        )?;
+        let mut log_builder = tauri_plugin_log::Builder::default().level(log::LevelFilter::Info);
+        if cli.mcp_stdio {
+          // stdout carries the MCP messages
+          log_builder = log_builder
+            .clear_targets()
+            .target(Target::new(TargetKind::Stderr))
+            .target(Target::new(TargetKind::LogDir { file_name: None }));
+        }
+
+        app.handle().plugin(log_builder.build())?;
+      }
+
+      if let Some(err) = &cli_error {
+        log::warn!("Ignoring the command line arguments: {err}");
       }
+      mcp_server::start(app.handle(), &cli);
 
       let show_item = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
 
@@ -145,10 +162,13 @@ pub fn run() {
         .menu(&menu)
         .on_menu_event(|app, event| match event.id().as_ref() {
           "quit" => {
-            tauri_plugin_mcp::destroy(app);
-            let _ = app.emit("mcp_plugin_destroyed", ());
-            app.cleanup_before_exit();
-            app.exit(0);
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+              tauri_plugin_mcp::shutdown(&app).await;
+              let _ = app.emit("mcp_plugin_destroyed", ());
+              app.cleanup_before_exit();
+              app.exit(0);
+            });
           }
           "settings" => {
             app::windows::settings::new_settings_window(app, None).unwrap();
@@ -0,0 +1,67 @@
+import type { SamplingMessage, SamplingRequest } from '@proj-airi/tauri-plugin-mcp'
+import type { ChatProvider } from '@xsai-ext/shared-providers'
+import type { Message } from '@xsai/shared-chat'
+
+import { useConsciousnessStore } from '@proj-airi/stage-ui/stores/modules/consciousness'
+import { useProvidersStore } from '@proj-airi/stage-ui/stores/providers'
+import { rejectSampling, respondSampling } from '@proj-airi/tauri-plugin-mcp'
+import { generateText } from '@xsai/generate-text'
+import { storeToRefs } from 'pinia'
+
+function toMessage(message: SamplingMessage): Message {
+  switch (message.content.type) {
+    case 'text':
+      return { role: message.role, content: message.content.text } as Message
+    case 'image':
+      return {
+        role: 'user',
+        content: [{ type: 'image_url', image_url: { url: `data:${message.content.mimeType};base64,${message.content.data}` } }],
+      } as Message
+    default:
+      throw new Error(`Unsupported sampling content: ${message.content.type}`)
+  }
+}
+
+/**
+ * Answers MCP `sampling/createMessage` requests with the chat model
+ * configured in the consciousness module.
+ */
+export function useMcpSampling() {
+  const providersStore = useProvidersStore()
+  const { activeProvider, activeModel } = storeToRefs(useConsciousnessStore())
+
+  async function complete(request: SamplingRequest) {
+    try {
+      if (!activeProvider.value || !activeModel.value)
+        throw new Error('No chat model is configured')
+
+      const chatProvider = await providersStore.getProviderInstance<ChatProvider>(activeProvider.value)
+      const messages: Message[] = request.messages.map(toMessage)
+      if (request.systemPrompt)
+        messages.unshift({ role: 'system', content: request.systemPrompt })
+
+      const response = await generateText({
+        ...chatProvider.chat(activeModel.value),
+        messages,
+        maxTokens: request.maxTokens,
+        temperature: request.temperature,
+        stop: request.stopSequences,
+      })
+
+      await respondSampling(request.samplingId, {
+        model: activeModel.value,
+        stopReason: response.finishReason === 'length' ? 'maxTokens' : 'endTurn',
+        role: 'assistant',
+        content: { type: 'text', text: response.text ?? '' },
+      })
+    }
+    catch (error) {
+      console.error('Failed to complete MCP sampling request', error)
+      await rejectSampling(request.samplingId, (error as Error).message)
+    }
+  }
+
+  return {
+    complete,
+  }
+}
@@ -56,6 +56,7 @@ export interface AiriTamagotchiEvents extends Events {
   'tauri-main:main:window-mode:fade-on-hover': true
   'tauri-main:main:window-mode:move': true
   'tauri-main:main:window-mode:resize': true
+  'tauri-main:main:say': string
 
   // from tauri-plugin-window-pass-through-on-hover
   'tauri-plugins:tauri-plugin-window-pass-through-on-hover:cursor-position': Point
@@ -64,6 +65,8 @@ export interface AiriTamagotchiEvents extends Events {
 
   // from tauri-plugin-ipc-audio-transcription-ort
   'tauri-plugins:tauri-plugin-ipc-audio-vad-ort:load-model-silero-vad-progress': [boolean, string, number, number, number]
+  'tauri-plugins:tauri-plugin-ipc-audio-vad-ort:speech-start': { sessionId: string, start: number, sampleRate: number }
+  'tauri-plugins:tauri-plugin-ipc-audio-vad-ort:speech-end': { sessionId: string, start: number, end: number, sampleRate: number, audio: number[] }
   // from tauri-plugin-ipc-audio-vad-ort
   'tauri-plugins:tauri-plugin-ipc-audio-transcription-ort:load-model-whisper-progress': [boolean, string, number, number, number]
 
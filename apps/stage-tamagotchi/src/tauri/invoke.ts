@@ -2,18 +2,65 @@ import type { InvokeArgs, InvokeOptions } from '@tauri-apps/api/core'
 
 import { invoke as tauriInvoke } from '@tauri-apps/api/core'
 
+/** Where the files of a model are loaded from, Hugging Face by default. */
+export interface ModelSource {
+  /**
+   * Directory laid out like the Hugging Face repo, relative paths are
+   * resolved against the resources of the app
+   */
+  localDir?: string
+  /** Never download, files have to be in `localDir` or the Hugging Face cache */
+  offline?: boolean
+  /**
+   * Expected SHA-256 hex digests by file path in the repo, files without a
+   * digest here or pinned in the plugin fail to load
+   */
+  sha256?: Record<string, string>
+}
+
+/**
+ * `silero` runs the Silero ONNX model, `energy` a model-free detector that
+ * works offline and at any sample rate.
+ */
+export type VadBackend = 'silero' | 'energy'
+
+export interface VadSessionConfig {
+  sampleRate: number
+  channels: number
+  positiveThreshold: number
+  negativeThreshold: number
+  minSpeechDurationMs: number
+  minSilenceDurationMs: number
+  prePaddingMs: number
+  postPaddingMs: number
+  maxSpeechDurationMs: number
+}
+
+export interface SpeechSegment {
+  /** In seconds */
+  start: number
+  /** In seconds */
+  end: number
+  probability: number
+  path?: string
+}
+
 export interface InvokeMethods {
   // app windows
   'open_settings_window': { args: undefined, options: undefined, returns: void }
   'open_chat_window': { args: undefined, options: undefined, returns: void }
 
   // Plugin - Audio Transcription
-  'plugin:ipc-audio-transcription-ort|load_ort_model_whisper': { args: { modelType: 'base' | 'largev3' | 'tiny' | 'medium' }, options: undefined, returns: void }
+  'plugin:ipc-audio-transcription-ort|load_ort_model_whisper': { args: { modelType: 'base' | 'largev3' | 'tiny' | 'medium', source?: ModelSource }, options: undefined, returns: void }
   'plugin:ipc-audio-transcription-ort|ipc_audio_transcription': { args: { chunk: number[], language: string }, options: undefined, returns: string }
 
   // Plugin - Audio VAD
-  'plugin:ipc-audio-vad-ort|load_ort_model_silero_vad': { args: undefined, options: undefined, returns: void }
-  'plugin:ipc-audio-vad-ort|ipc_audio_vad': { args: { inputData: { input: number[], sr: number, state: number[] } }, options: undefined, returns: number }
+  'plugin:ipc-audio-vad-ort|load_ort_model_silero_vad': { args: { backend?: VadBackend, source?: ModelSource } | undefined, options: undefined, returns: void }
+  'plugin:ipc-audio-vad-ort|ipc_audio_vad': { args: { inputData: { input: number[], sr: number, state: number[], channels?: number } }, options: undefined, returns: { output: number[], state: number[] } }
+  'plugin:ipc-audio-vad-ort|create_session': { args: { config?: Partial<VadSessionConfig> }, options: undefined, returns: string }
+  'plugin:ipc-audio-vad-ort|push_audio': { args: { sessionId: string, samples: number[] }, options: undefined, returns: number[] }
+  'plugin:ipc-audio-vad-ort|close_session': { args: { sessionId: string }, options: undefined, returns: void }
+  'plugin:ipc-audio-vad-ort|detect_speech_in_file': { args: { path: string, config?: Partial<VadSessionConfig>, outputDir?: string }, options: undefined, returns: SpeechSegment[] }
 
   // Plugin - Window Pass through on hover
   'plugin:window-pass-through-on-hover|start_tracing_cursor': { args: undefined, options: undefined, returns: void }
@@ -0,0 +1,258 @@
+use std::{
+  fs::File,
+  io::{BufWriter, Write},
+  path::Path,
+};
+
+use anyhow::{Result, anyhow};
+use byteorder::{LittleEndian, WriteBytesExt};
+use rubato::{FftFixedIn, Resampler};
+use symphonia::core::{
+  audio::SampleBuffer,
+  codecs::{CODEC_TYPE_NULL, DecoderOptions},
+  errors::Error as SymphoniaError,
+  formats::FormatOptions,
+  io::MediaSourceStream,
+  meta::MetadataOptions,
+  probe::Hint,
+};
+
+const RESAMPLE_CHUNK_SIZE: usize = 1024;
+
+/// Average interleaved frames of `channels` samples into mono.
+pub fn downmix(
+  samples: &[f32],
+  channels: u16,
+) -> Result<Vec<f32>> {
+  let channels = usize::from(channels);
+  if channels == 0 {
+    return Err(anyhow!("Channel count must be at least 1"));
+  }
+  if samples.len() % channels != 0 {
+    return Err(anyhow!(
+      "Got {} samples, which is not a whole number of frames of {channels} interleaved channels",
+      samples.len()
+    ));
+  }
+  if channels == 1 {
+    return Ok(samples.to_vec());
+  }
+
+  Ok(
+    samples
+      .chunks_exact(channels)
+      .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+      .collect(),
+  )
+}
+
+/// Resampler for mono audio arriving in chunks of any length.
+///
+/// The delay of the resampler is dropped from the output, so the samples
+/// it returns line up with the input from the first one on.
+pub struct StreamResampler {
+  resampler: FftFixedIn<f32>,
+  from:      u64,
+  to:        u64,
+  /// Input not filling a chunk of the resampler yet.
+  pending:   Vec<f32>,
+  /// Output samples still to drop.
+  delay:     usize,
+  consumed:  u64,
+  produced:  u64,
+}
+
+impl StreamResampler {
+  pub fn new(
+    from: u32,
+    to: u32,
+  ) -> Result<Self> {
+    if from == 0 {
+      return Err(anyhow!("Sample rate must be above 0 Hz"));
+    }
+
+    let resampler = FftFixedIn::<f32>::new(from as usize, to as usize, RESAMPLE_CHUNK_SIZE, 2, 1)?;
+    Ok(Self {
+      delay: resampler.output_delay(),
+      resampler,
+      from: u64::from(from),
+      to: u64::from(to),
+      pending: Vec::new(),
+      consumed: 0,
+      produced: 0,
+    })
+  }
+
+  /// Resample newly arrived samples. Up to a chunk of input is held back
+  /// until more arrives or the stream is flushed.
+  pub fn process(
+    &mut self,
+    samples: &[f32],
+  ) -> Result<Vec<f32>> {
+    self.pending.extend_from_slice(samples);
+    self.consumed += samples.len() as u64;
+
+    let mut output = Vec::new();
+    let mut offset = 0;
+    while self.pending.len() - offset >= self.resampler.input_frames_next() {
+      let next = self.resampler.input_frames_next();
+      let resampled = self
+        .resampler
+        .process(&[&self.pending[offset..offset + next]], None)?;
+      offset += next;
+      self.append(&resampled[0], &mut output);
+    }
+    self.pending.drain(..offset);
+
+    Ok(output)
+  }
+
+  /// Resample what is left at the end of the stream.
+  pub fn flush(&mut self) -> Result<Vec<f32>> {
+    let expected = self.consumed * self.to / self.from;
+
+    let mut output = Vec::new();
+    let pending = std::mem::take(&mut self.pending);
+    let resampled = self
+      .resampler
+      .process_partial(Some(&[pending.as_slice()]), None)?;
+    self.append(&resampled[0], &mut output);
+    while self.produced < expected {
+      let resampled = self
+        .resampler
+        .process_partial::<&[f32]>(None, None)?;
+      self.append(&resampled[0], &mut output);
+    }
+
+    let excess = (self.produced - expected) as usize;
+    output.truncate(output.len().saturating_sub(excess));
+    self.produced = expected;
+    Ok(output)
+  }
+
+  fn append(
+    &mut self,
+    resampled: &[f32],
+    output: &mut Vec<f32>,
+  ) {
+    let skip = self.delay.min(resampled.len());
+    self.delay -= skip;
+    output.extend_from_slice(&resampled[skip..]);
+    self.produced += (resampled.len() - skip) as u64;
+  }
+}
+
+/// Resample a whole clip of mono audio.
+pub fn resample(
+  samples: &[f32],
+  from: u32,
+  to: u32,
+) -> Result<Vec<f32>> {
+  if from == to {
+    return Ok(samples.to_vec());
+  }
+
+  let mut resampler = StreamResampler::new(from, to)?;
+  let mut output = resampler.process(samples)?;
+  output.extend(resampler.flush()?);
+  Ok(output)
+}
+
+/// Decode an audio file into mono samples, returning them with their sample
+/// rate.
+pub fn decode_file(path: &Path) -> Result<(Vec<f32>, u32)> {
+  let file = File::open(path)?;
+  let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+  let mut hint = Hint::new();
+  if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+    hint.with_extension(extension);
+  }
+
+  let probed = symphonia::default::get_probe().format(
+    &hint,
+    stream,
+    &FormatOptions::default(),
+    &MetadataOptions::default(),
+  )?;
+  let mut format = probed.format;
+
+  let track = format
+    .tracks()
+    .iter()
+    .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+    .ok_or_else(|| anyhow!("No audio track found in {}", path.display()))?;
+  let track_id = track.id;
+  let sample_rate = track
+    .codec_params
+    .sample_rate
+    .ok_or_else(|| anyhow!("Unknown sample rate of {}", path.display()))?;
+
+  let mut decoder =
+    symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+  let mut samples = Vec::new();
+  loop {
+    let packet = match format.next_packet() {
+      Ok(packet) => packet,
+      Err(SymphoniaError::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+      Err(err) => return Err(err.into()),
+    };
+    if packet.track_id() != track_id {
+      continue;
+    }
+
+    let decoded = match decoder.decode(&packet) {
+      Ok(decoded) => decoded,
+      // Skip corrupted packets instead of failing the whole file
+      Err(SymphoniaError::DecodeError(_)) => continue,
+      Err(err) => return Err(err.into()),
+    };
+
+    let channels = decoded.spec().channels.count();
+    let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+    buffer.copy_interleaved_ref(decoded);
+
+    samples.extend(
+      buffer
+        .samples()
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+    );
+  }
+
+  Ok((samples, sample_rate))
+}
+
+/// Write mono samples as a 16-bit PCM WAV file.
+pub fn write_wav(
+  path: &Path,
+  samples: &[f32],
+  sample_rate: u32,
+) -> Result<()> {
+  let data_size = u32::try_from(samples.len() * 2)
+    .map_err(|_| anyhow!("Too many samples for a WAV file: {}", samples.len()))?;
+
+  let mut writer = BufWriter::new(File::create(path)?);
+  writer.write_all(b"RIFF")?;
+  writer.write_u32::<LittleEndian>(36 + data_size)?;
+  writer.write_all(b"WAVE")?;
+
+  writer.write_all(b"fmt ")?;
+  writer.write_u32::<LittleEndian>(16)?;
+  writer.write_u16::<LittleEndian>(1)?; // PCM
+  writer.write_u16::<LittleEndian>(1)?; // Mono
+  writer.write_u32::<LittleEndian>(sample_rate)?;
+  writer.write_u32::<LittleEndian>(sample_rate * 2)?; // Byte rate
+  writer.write_u16::<LittleEndian>(2)?; // Block align
+  writer.write_u16::<LittleEndian>(16)?; // Bits per sample
+
+  writer.write_all(b"data")?;
+  writer.write_u32::<LittleEndian>(data_size)?;
+  for sample in samples {
+    writer.write_i16::<LittleEndian>((sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16)?;
+  }
+  writer.flush()?;
+
+  Ok(())
+}
@@ -57,7 +57,7 @@ impl<R: Runtime> ProgressEmitter for tauri::WebviewWindow<R> {
       event_name.as_str(),
       (false, filename, progress, total_size, current_size),
     ) {
-      error!("Failed to emit model-load-progress: {:?}", err);
+      error!("Failed to emit model-load-progress: {err:?}");
     }
   }
 
@@ -67,7 +67,7 @@ impl<R: Runtime> ProgressEmitter for tauri::WebviewWindow<R> {
     filename: String,
   ) {
     if let Err(err) = self.emit(event_name.as_str(), (true, filename, 100.0)) {
-      error!("Failed to emit model-load-done: {:?}", err);
+      error!("Failed to emit model-load-done: {err:?}");
     }
   }
 }
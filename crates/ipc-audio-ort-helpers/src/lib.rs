@@ -0,0 +1,5 @@
+//! Helpers shared by the `tauri-plugin-ipc-audio-*-ort` plugins.
+
+pub mod audio;
+pub mod huggingface;
+pub mod model_source;
@@ -0,0 +1,245 @@
+use std::{
+  collections::HashMap,
+  fs::File,
+  io,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use hf_hub::{Repo, RepoType, api::sync::ApiRepo};
+use log::{error, info, warn};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tauri::{Manager, Runtime};
+
+use crate::huggingface::create_progress_emitter;
+
+/// Where the files of a model are loaded from, Hugging Face by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelSource {
+  /// Directory laid out like the Hugging Face repo, e.g. containing
+  /// `onnx/model.onnx`, used instead of the Hub. Relative paths are resolved
+  /// against the resources of the app, for models bundled with it.
+  pub local_dir: Option<PathBuf>,
+  /// Never touch the network, files have to be in `local_dir` or already in
+  /// the Hugging Face cache. Also on when `HF_HUB_OFFLINE=1` is set.
+  pub offline:   bool,
+  /// Expected SHA-256 hex digests by file path in the repo, taking
+  /// precedence over the ones pinned for the model. Needed for files of a
+  /// `local_dir` holding a different model and for files not pinned.
+  pub sha256:    HashMap<String, String>,
+}
+
+impl ModelSource {
+  pub fn is_offline(&self) -> bool {
+    self.offline
+      || std::env::var("HF_HUB_OFFLINE").is_ok_and(|value| matches!(value.as_str(), "1" | "true"))
+  }
+
+  /// Resolve a relative `local_dir` against the resource dir of the app.
+  pub fn resolve<R: Runtime, M: Manager<R>>(
+    mut self,
+    manager: &M,
+  ) -> Result<Self> {
+    if let Some(local_dir) = &self.local_dir {
+      if local_dir.is_relative() {
+        self.local_dir = Some(manager.path().resource_dir()?.join(local_dir));
+      }
+    }
+
+    Ok(self)
+  }
+}
+
+/// Commit a model is loaded from and the SHA-256 hex digests of its files
+/// at that commit. A file without a digest here or in `ModelSource::sha256`
+/// fails to load, the error names its digest so it can be pinned.
+#[derive(Debug, Clone, Copy)]
+pub struct ModelPin {
+  /// Commit hash, a branch would let the files change under the digests.
+  pub revision: &'static str,
+  pub sha256:   &'static [(&'static str, &'static str)],
+}
+
+impl ModelPin {
+  fn sha256(
+    &self,
+    file_sub_path: &str,
+  ) -> Option<&'static str> {
+    self
+      .sha256
+      .iter()
+      .find(|(path, _)| *path == file_sub_path)
+      .map(|(_, sha256)| *sha256)
+  }
+}
+
+/// Files of one model repo, looked up in `local_dir`, the Hugging Face cache
+/// or downloaded, in that order.
+pub struct ModelFiles<R: Runtime> {
+  model_id:   String,
+  pin:        ModelPin,
+  source:     ModelSource,
+  cache_repo: hf_hub::CacheRepo,
+  /// Not set in offline mode or with `local_dir`, so no client is built.
+  repo:       Option<ApiRepo>,
+  window:     tauri::WebviewWindow<R>,
+  event_name: String,
+}
+
+impl<R: Runtime> ModelFiles<R> {
+  pub fn new(
+    model_id: &str,
+    pin: ModelPin,
+    source: ModelSource,
+    window: tauri::WebviewWindow<R>,
+    event_name: &str,
+  ) -> Result<Self> {
+    let repo = Repo::with_revision(
+      model_id.to_string(),
+      RepoType::Model,
+      pin.revision.to_string(),
+    );
+    let cache_repo = hf_hub::Cache::from_env().repo(repo.clone());
+
+    let api_repo = if source.local_dir.is_some() || source.is_offline() {
+      info!("Loading {model_id} without network access");
+      None
+    } else {
+      let api = hf_hub::api::sync::ApiBuilder::new()
+        .with_progress(false)
+        .build()?;
+      Some(api.repo(repo))
+    };
+
+    Ok(Self {
+      model_id: model_id.to_string(),
+      pin,
+      source,
+      cache_repo,
+      repo: api_repo,
+      window,
+      event_name: event_name.to_string(),
+    })
+  }
+
+  /// Path of a file of the repo, downloading it when needed. The file is
+  /// verified against its digest on every load, cached or not.
+  pub fn get(
+    &self,
+    file_sub_path: &str,
+  ) -> Result<PathBuf> {
+    let expected = self
+      .source
+      .sha256
+      .get(file_sub_path)
+      .map(String::as_str)
+      .or_else(|| self.pin.sha256(file_sub_path));
+
+    if let Some(local_dir) = &self.source.local_dir {
+      let path = local_dir.join(file_sub_path);
+      if !path.is_file() {
+        return Err(anyhow!(
+          "{file_sub_path} of {} not found in {}",
+          self.model_id,
+          local_dir.display()
+        ));
+      }
+      self.check(file_sub_path, &path, expected)?;
+      return Ok(path);
+    }
+
+    if let Some(path) = self.cache_repo.get(file_sub_path) {
+      match self.check(file_sub_path, &path, expected) {
+        Ok(()) => return Ok(path),
+        // Download it again below when online, unless it has no digest to match
+        Err(err) if self.repo.is_some() && expected.is_some() => {
+          warn!("Discarding the cached {file_sub_path}: {err}");
+          remove_cached(&path);
+        },
+        Err(err) => return Err(err),
+      }
+    }
+
+    let Some(repo) = &self.repo else {
+      return Err(anyhow!(
+        "{file_sub_path} of {} is not in the Hugging Face cache and offline mode is on",
+        self.model_id
+      ));
+    };
+
+    let path = repo
+      .download_with_progress(
+        file_sub_path,
+        create_progress_emitter(
+          self.window.clone(),
+          &self.event_name,
+          file_sub_path.to_string(),
+        ),
+      )
+      .map_err(|e| anyhow!("failed to download {file_sub_path}: {e}"))?;
+
+    if let Err(err) = self.check(file_sub_path, &path, expected) {
+      // Don't leave the broken file in the cache, the next load downloads it again.
+      // An unpinned one is kept, so pinning it doesn't need another download.
+      if expected.is_some() {
+        remove_cached(&path);
+      }
+      return Err(err);
+    }
+
+    Ok(path)
+  }
+
+  fn check(
+    &self,
+    file_sub_path: &str,
+    path: &Path,
+    expected: Option<&str>,
+  ) -> Result<()> {
+    let actual = sha256(path)?;
+    let Some(expected) = expected else {
+      let message = format!(
+        "No SHA-256 pinned for {file_sub_path} of {} at {}, refusing to load it (got {actual})",
+        self.model_id, self.pin.revision
+      );
+      error!("{message}");
+      return Err(anyhow!(message));
+    };
+
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+      let message = format!(
+        "SHA-256 mismatch for {file_sub_path} at {}: expected {expected}, got {actual}",
+        path.display()
+      );
+      error!("{message}");
+      return Err(anyhow!(message));
+    }
+
+    info!("Verified SHA-256 of {file_sub_path}");
+    Ok(())
+  }
+}
+
+/// Remove a file from the Hugging Face cache, both the snapshot link and the
+/// blob it points to.
+fn remove_cached(path: &Path) {
+  let blob = std::fs::canonicalize(path).ok();
+  let _ = std::fs::remove_file(path);
+  if let Some(blob) = blob {
+    let _ = std::fs::remove_file(blob);
+  }
+}
+
+fn sha256(path: &Path) -> Result<String> {
+  let mut hasher = Sha256::new();
+  io::copy(&mut File::open(path)?, &mut hasher)?;
+  Ok(
+    hasher
+      .finalize()
+      .iter()
+      .map(|byte| format!("{byte:02x}"))
+      .collect(),
+  )
+}
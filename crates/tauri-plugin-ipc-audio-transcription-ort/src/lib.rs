@@ -1,4 +1,4 @@
-use std::sync::Mutex;
+use std::{path::Path, sync::Mutex};
 
 use clap::ValueEnum;
 use log::info;
@@ -8,9 +8,13 @@ use tauri::{
   plugin::{Builder as PluginBuilder, TauriPlugin},
 };
 
-mod helpers;
 mod models;
 
+use ipc_audio_ort_helpers::audio;
+pub use ipc_audio_ort_helpers::model_source::ModelSource;
+
+use crate::models::whisper::whisper_processor::SAMPLE_RATE;
+
 #[derive(Default)]
 struct AppDataWhisperProcessor {
   whisper_processor: Option<models::whisper::whisper::WhisperPipeline>,
@@ -21,13 +25,19 @@ use crate::models::{
   whisper::{self, whisper::WhichModel},
 };
 
+/// Load a Whisper model, `source` tells where its files come from.
 #[tauri::command]
 async fn load_ort_model_whisper<R: Runtime>(
   app: tauri::AppHandle<R>,
   window: tauri::WebviewWindow<R>,
   model_type: Option<String>,
+  source: Option<ModelSource>,
 ) -> Result<(), String> {
   info!("Loading models...");
+  let source = source
+    .unwrap_or_default()
+    .resolve(&app)
+    .map_err(|e| e.to_string())?;
 
   {
     let data = app.state::<Mutex<AppDataWhisperProcessor>>();
@@ -47,6 +57,7 @@ async fn load_ort_model_whisper<R: Runtime>(
         .as_str(),
       true,
     )?),
+    source,
   ) {
     Ok(p) => {
       let data = app.state::<Mutex<AppDataWhisperProcessor>>();
@@ -70,6 +81,15 @@ async fn ipc_audio_transcription<R: Runtime>(
   app: tauri::AppHandle<R>,
   chunk: Vec<f32>,
   language: Option<String>,
+) -> Result<String, String> {
+  transcribe(&app, chunk.as_slice(), language)
+}
+
+/// Transcribe 16 kHz mono samples with the loaded Whisper model.
+pub fn transcribe<R: Runtime>(
+  app: &tauri::AppHandle<R>,
+  chunk: &[f32],
+  language: Option<String>,
 ) -> Result<String, String> {
   info!("Processing audio transcription...");
 
@@ -91,7 +111,7 @@ async fn ipc_audio_transcription<R: Runtime>(
   config.language = language;
 
   let transcription = processor
-    .transcribe(chunk.as_slice(), &config)
+    .transcribe(chunk, &config)
     .map_err(|e| e.to_string())?;
 
   info!("Transcription completed: {}", transcription);
@@ -99,6 +119,30 @@ async fn ipc_audio_transcription<R: Runtime>(
   Ok(transcription)
 }
 
+/// Decode an audio file and transcribe it in windows of 30 seconds, the
+/// longest input Whisper takes at once.
+///
+/// Blocks until the whole file is transcribed.
+pub fn transcribe_file<R: Runtime>(
+  app: &tauri::AppHandle<R>,
+  path: &Path,
+  language: Option<String>,
+) -> Result<String, String> {
+  let (samples, sample_rate) = audio::decode_file(path).map_err(|e| e.to_string())?;
+  let samples =
+    audio::resample(&samples, sample_rate, SAMPLE_RATE as u32).map_err(|e| e.to_string())?;
+
+  let mut transcription = Vec::new();
+  for window in samples.chunks(SAMPLE_RATE * 30) {
+    let text = transcribe(app, window, language.clone())?;
+    if !text.trim().is_empty() {
+      transcription.push(text.trim().to_string());
+    }
+  }
+
+  Ok(transcription.join(" "))
+}
+
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
   PluginBuilder::new("ipc-audio-transcription-ort")
     .setup(|app, _| {
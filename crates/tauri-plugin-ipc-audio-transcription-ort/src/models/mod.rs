@@ -1,5 +1,6 @@
 pub mod whisper;
 
+use ipc_audio_ort_helpers::model_source::{ModelFiles, ModelSource};
 use log::info;
 use ort::execution_providers::{CUDAExecutionProvider, CoreMLExecutionProvider, ExecutionProvider};
 use tauri::Runtime;
@@ -7,6 +8,7 @@ use tauri::Runtime;
 pub fn new_whisper_processor<R: Runtime>(
   window: tauri::WebviewWindow<R>,
   model_type: Option<whisper::whisper::WhichModel>,
+  source: ModelSource,
 ) -> anyhow::Result<whisper::whisper::WhisperPipeline> {
   let cuda = CUDAExecutionProvider::default().with_device_id(0);
   let coreml = CoreMLExecutionProvider::default();
@@ -22,6 +24,13 @@ pub fn new_whisper_processor<R: Runtime>(
   });
 
   info!("Loading whisper model: {:?}", whisper_model);
-  let (model_id, revision) = whisper_model.model_and_revision();
-  whisper::whisper::WhisperPipeline::new(whisper_model, model_id, revision, window)
+  let (model_id, pin) = whisper_model.model_and_pin();
+  let files = ModelFiles::new(
+    model_id,
+    pin,
+    source,
+    window,
+    "tauri-plugins:tauri-plugin-ipc-audio-transcription-ort:load-model-whisper-progress",
+  )?;
+  whisper::whisper::WhisperPipeline::new(whisper_model, &files)
 }
@@ -2,11 +2,7 @@ use std::{borrow::Cow, collections::HashMap, path::PathBuf};
 
 use anyhow::{Result, anyhow};
 use clap::ValueEnum;
-use hf_hub::{
-  Repo,
-  RepoType,
-  api::sync::{Api, ApiBuilder},
-};
+use ipc_audio_ort_helpers::model_source::{ModelFiles, ModelPin};
 use ndarray::{Array2, ArrayView3, Axis, s};
 use ort::{
   execution_providers::{
@@ -23,7 +19,6 @@ use tauri::Runtime;
 use tokenizers::Tokenizer;
 
 use super::whisper_processor::WhisperProcessor;
-use crate::helpers::huggingface::create_progress_emitter;
 
 // Helper function to provide a default value of true for serde
 const fn default_true() -> bool {
@@ -205,24 +200,6 @@ pub fn whisper_language_to_code(language: &str) -> Result<String> {
   Err(anyhow!("Language '{}' is not supported.", language))
 }
 
-fn get_or_download_file<R: Runtime>(
-  cache_repo: &hf_hub::CacheRepo,
-  repo: &hf_hub::api::sync::ApiRepo,
-  window: tauri::WebviewWindow<R>,
-  file_sub_path: &str,
-  event_name: &str,
-) -> Result<PathBuf> {
-  match cache_repo.get(file_sub_path) {
-    Some(p) => Ok(p),
-    None => repo
-      .download_with_progress(
-        file_sub_path,
-        create_progress_emitter(window.clone(), event_name, file_sub_path.to_string()),
-      )
-      .map_err(|e| anyhow!("failed to download {}: {}", file_sub_path, e)),
-  }
-}
-
 pub struct Whisper {
   encoder_session: Session,
   decoder_session: Session,
@@ -230,64 +207,11 @@ pub struct Whisper {
 }
 
 impl Whisper {
-  pub fn new<R: Runtime>(
-    model_id: &str,
-    revision: &str,
-    window: tauri::WebviewWindow<R>,
-  ) -> Result<Self> {
-    let cache_api = hf_hub::Cache::from_env();
-    let cache_repo = cache_api.repo(Repo::with_revision(
-      model_id.to_string(),
-      RepoType::Model,
-      revision.to_string(),
-    ));
-
-    let api = ApiBuilder::new().with_progress(false).build()?;
-    let repo = api.repo(Repo::with_revision(
-      model_id.to_string(),
-      RepoType::Model,
-      revision.to_string(),
-    ));
-
-    let encoder_model_path = get_or_download_file(
-      &cache_repo,
-      &repo,
-      window.clone(),
-      "onnx/encoder_model.onnx",
-      "tauri-plugins:tauri-plugin-ipc-audio-transcription-ort:load-model-whisper-progress",
-    )?;
-
-    let decoder_model_path = get_or_download_file(
-      &cache_repo,
-      &repo,
-      window.clone(),
-      "onnx/decoder_model.onnx",
-      "tauri-plugins:tauri-plugin-ipc-audio-transcription-ort:load-model-whisper-progress",
-    )?;
-
-    let config_path = match cache_repo.get("config.json") {
-      Some(path) => path,
-      None => repo.download_with_progress(
-        "config.json",
-        create_progress_emitter(
-          window.clone(),
-          "tauri-plugins:tauri-plugin-ipc-audio-transcription-ort:load-model-whisper-progress",
-          "config.json".to_string(),
-        ),
-      )?,
-    };
-
-    let tokenizer_config_path = match cache_repo.get("tokenizer_config.json") {
-      Some(path) => path,
-      None => repo.download_with_progress(
-        "tokenizer_config.json",
-        create_progress_emitter(
-          window.clone(),
-          "tauri-plugins:tauri-plugin-ipc-audio-transcription-ort:load-model-whisper-progress",
-          "tokenizer_config.json".to_string(),
-        ),
-      )?,
-    };
+  pub fn new<R: Runtime>(files: &ModelFiles<R>) -> Result<Self> {
+    let encoder_model_path = files.get("onnx/encoder_model.onnx")?;
+    let decoder_model_path = files.get("onnx/decoder_model.onnx")?;
+    let config_path = files.get("config.json")?;
+    let tokenizer_config_path = files.get("tokenizer_config.json")?;
 
     let encoder_session = Self::create_optimized_session(encoder_model_path)?;
     let decoder_session = Self::create_optimized_session(decoder_model_path)?;
@@ -452,6 +376,14 @@ impl Whisper {
   }
 }
 
+/// No commit or digests are pinned for the Whisper repos yet, so a model only
+/// loads with the digests of its files in `ModelSource::sha256`, the load
+/// error names them.
+const NOT_PINNED: ModelPin = ModelPin {
+  revision: "main",
+  sha256:   &[],
+};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
 pub enum WhichModel {
   Tiny,
@@ -463,14 +395,15 @@ pub enum WhichModel {
 }
 
 impl WhichModel {
-  pub const fn model_and_revision(self) -> (&'static str, &'static str) {
+  /// Repo of the model and the commit its files are loaded from.
+  pub const fn model_and_pin(self) -> (&'static str, ModelPin) {
     match self {
-      Self::Tiny => ("onnx-community/whisper-tiny-ONNX", "main"),
-      Self::Base => ("onnx-community/whisper-base-ONNX", "main"),
-      Self::Small => ("onnx-community/whisper-small-ONNX", "main"),
-      Self::Medium => ("onnx-community/whisper-medium-ONNX", "main"),
-      Self::LargeV3 => ("onnx-community/whisper-large-v3-ONNX", "main"),
-      Self::LargeV3Turbo => ("onnx-community/whisper-large-v3-turbo-ONNX", "main"),
+      Self::Tiny => ("onnx-community/whisper-tiny-ONNX", NOT_PINNED),
+      Self::Base => ("onnx-community/whisper-base-ONNX", NOT_PINNED),
+      Self::Small => ("onnx-community/whisper-small-ONNX", NOT_PINNED),
+      Self::Medium => ("onnx-community/whisper-medium-ONNX", NOT_PINNED),
+      Self::LargeV3 => ("onnx-community/whisper-large-v3-ONNX", NOT_PINNED),
+      Self::LargeV3Turbo => ("onnx-community/whisper-large-v3-turbo-ONNX", NOT_PINNED),
     }
   }
 }
@@ -485,36 +418,14 @@ pub struct WhisperPipeline {
 impl WhisperPipeline {
   pub fn new<R: Runtime>(
     which_model: WhichModel,
-    model_id: &str,
-    revision: &str,
-    window: tauri::WebviewWindow<R>,
+    files: &ModelFiles<R>,
   ) -> Result<Self> {
-    let model = Whisper::new(model_id, revision, window.clone())?;
+    let model = Whisper::new(files)?;
 
     // Initialize our new processor
     let processor = WhisperProcessor::new(which_model)?;
 
-    let cache_api = hf_hub::Cache::from_env();
-    let cache_repo = cache_api.repo(Repo::with_revision(
-      model_id.to_string(),
-      RepoType::Model,
-      revision.to_string(),
-    ));
-
-    let api = Api::new()?;
-    let repo = api.repo(hf_hub::Repo::with_revision(
-      model_id.to_string(),
-      hf_hub::RepoType::Model,
-      revision.to_string(),
-    ));
-
-    let tokenizer_path = get_or_download_file(
-      &cache_repo,
-      &repo,
-      window.clone(),
-      "tokenizer.json",
-      "tauri-plugins:tauri-plugin-ipc-audio-transcription-ort:load-model-whisper-progress",
-    )?;
+    let tokenizer_path = files.get("tokenizer.json")?;
 
     let tokenizer = Tokenizer::from_file(tokenizer_path)
       .map_err(|e| anyhow!("Failed to load tokenizer: {}", e))?;
@@ -6,7 +6,7 @@ use rustfft::{FftPlanner, num_complex::Complex};
 use super::whisper::WhichModel;
 
 // Constants from the Whisper paper/implementation
-const SAMPLE_RATE: usize = 16000;
+pub const SAMPLE_RATE: usize = 16000;
 const N_FFT: usize = 400;
 const N_MELS: usize = 80; // <--- Crucial: Use 80 for base, 128 for large-v3
 const HOP_LENGTH: usize = 160;
@@ -1,4 +1,11 @@
-const COMMANDS: &[&str] = &["load_ort_model_silero_vad", "ipc_audio_vad"];
+const COMMANDS: &[&str] = &[
+  "load_ort_model_silero_vad",
+  "ipc_audio_vad",
+  "create_session",
+  "push_audio",
+  "close_session",
+  "detect_speech_in_file",
+];
 
 fn main() {
   tauri_plugin::Builder::new(COMMANDS).build();
@@ -0,0 +1,180 @@
+use std::{
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+use anyhow::Result;
+use ipc_audio_ort_helpers::audio;
+use log::info;
+use serde::Serialize;
+
+use crate::{
+  models::VoiceActivityDetector,
+  session::{VadSession, VadSessionConfig, VadSessionEvent},
+};
+
+/// A region of speech found in an audio file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeechSegment {
+  /// Start in seconds, including the pre-padding.
+  pub start:       f64,
+  /// End in seconds, including the post-padding.
+  pub end:         f64,
+  /// Mean speech probability of the windows in the segment.
+  pub probability: f32,
+  /// WAV file the segment was written to.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub path:        Option<PathBuf>,
+}
+
+/// Decode an audio file and run VAD over all of it, cutting it into speech
+/// segments the same way a streaming session does.
+///
+/// With `output_dir`, every segment is also written there as
+/// `<file name>-<index>.wav`, mono at the sample rate of the file.
+pub fn detect_file(
+  detector: Arc<dyn VoiceActivityDetector>,
+  path: &Path,
+  config: VadSessionConfig,
+  output_dir: Option<&Path>,
+) -> Result<Vec<SpeechSegment>> {
+  let (samples, sample_rate) = audio::decode_file(path)?;
+  info!(
+    "Decoded {} ({} samples at {sample_rate} Hz)",
+    path.display(),
+    samples.len()
+  );
+
+  let config = VadSessionConfig {
+    sample_rate,
+    channels: 1,
+    ..config
+  };
+  let mut session = VadSession::new(path.display().to_string(), String::new(), config, detector)?;
+  let model_sample_rate = f64::from(session.model_sample_rate());
+  let window_size = session.window_size() as u64;
+
+  let (probabilities, mut events) = session.push(&samples)?;
+  events.extend(session.close());
+
+  if let Some(output_dir) = output_dir {
+    std::fs::create_dir_all(output_dir)?;
+  }
+  let stem = path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().into_owned())
+    .unwrap_or_else(|| "segment".to_string());
+
+  let mut segments = Vec::new();
+  for event in events {
+    let VadSessionEvent::SpeechEnd(payload) = event else {
+      continue;
+    };
+
+    let first = (payload.start / window_size) as usize;
+    let last = (payload.end.div_ceil(window_size) as usize).min(probabilities.len());
+    let windows = probabilities.get(first..last).unwrap_or_default();
+    let probability = if windows.is_empty() {
+      0.0
+    } else {
+      windows.iter().sum::<f32>() / windows.len() as f32
+    };
+
+    let start = payload.start as f64 / model_sample_rate;
+    let end = payload.end as f64 / model_sample_rate;
+
+    let path = match output_dir {
+      Some(output_dir) => {
+        let from = ((start * f64::from(sample_rate)) as usize).min(samples.len());
+        let to = ((end * f64::from(sample_rate)) as usize).clamp(from, samples.len());
+        let path = output_dir.join(format!("{stem}-{:03}.wav", segments.len()));
+        audio::write_wav(&path, &samples[from..to], sample_rate)?;
+        Some(path)
+      },
+      None => None,
+    };
+
+    segments.push(SpeechSegment {
+      start,
+      end,
+      probability,
+      path,
+    });
+  }
+
+  info!(
+    "Found {} speech segments in {}",
+    segments.len(),
+    path.display()
+  );
+  Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use ipc_audio_ort_helpers::audio;
+
+  use super::detect_file;
+  use crate::{
+    models::energy_vad::Detector,
+    session::tests::{SAMPLE_RATE, config, silence, tone},
+  };
+
+  #[test]
+  fn detects_speech_segments_in_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("speech.wav");
+    let samples = [
+      silence(600),
+      tone(900),
+      silence(900),
+      tone(600),
+      silence(600),
+    ]
+    .concat();
+    audio::write_wav(&path, &samples, SAMPLE_RATE).unwrap();
+
+    let output_dir = dir.path().join("segments");
+    let segments = detect_file(Arc::new(Detector), &path, config(), Some(&output_dir)).unwrap();
+
+    let bounds: Vec<(f64, f64)> = segments
+      .iter()
+      .map(|segment| (segment.start, segment.end))
+      .collect();
+    assert_eq!(bounds, [(0.6, 1.5), (2.4, 3.0)]);
+    for (index, segment) in segments.iter().enumerate() {
+      assert!(segment.probability > 0.5);
+      let written = segment.path.as_ref().unwrap();
+      assert_eq!(*written, output_dir.join(format!("speech-{index:03}.wav")));
+
+      let (audio, sample_rate) = audio::decode_file(written).unwrap();
+      assert_eq!(sample_rate, SAMPLE_RATE);
+      let expected = ((segment.end - segment.start) * f64::from(SAMPLE_RATE)).round() as usize;
+      assert_eq!(audio.len(), expected);
+    }
+  }
+
+  #[test]
+  fn detects_speech_in_files_at_other_sample_rates() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("speech.wav");
+    let resampled = audio::resample(
+      &[silence(600), tone(900), silence(900)].concat(),
+      SAMPLE_RATE,
+      44100,
+    )
+    .unwrap();
+    audio::write_wav(&path, &resampled, 44100).unwrap();
+
+    let segments = detect_file(Arc::new(Detector), &path, config(), None).unwrap();
+
+    // Windows are 30 ms at any rate, the resampler may smear an edge by one
+    assert_eq!(segments.len(), 1);
+    assert!((segments[0].start - 0.6).abs() <= 0.031);
+    assert!((segments[0].end - 1.5).abs() <= 0.031);
+    assert!(segments[0].path.is_none());
+  }
+}
@@ -1,50 +1,130 @@
-use std::sync::Mutex;
+use std::{
+  collections::HashMap,
+  path::{Component, Path, PathBuf},
+  sync::{
+    Arc,
+    Mutex,
+    PoisonError,
+    atomic::{AtomicU64, Ordering},
+  },
+};
 
-use log::info;
+use log::{error, info};
 use tauri::{
+  Emitter,
   Manager,
   Runtime,
   plugin::{Builder as PluginBuilder, TauriPlugin},
 };
 
-mod helpers;
+mod file;
 mod models;
+mod session;
+
+pub use ipc_audio_ort_helpers::model_source::ModelSource;
 
-use crate::models::{
-  new_silero_vad_processor,
-  silero_vad::{VADInferenceInput, VADInferenceResult},
+pub use crate::{
+  file::SpeechSegment,
+  models::{VadBackend, VoiceActivityDetector},
+  session::{SpeechEndPayload, SpeechStartPayload, VadSessionConfig},
 };
+use crate::{
+  models::{
+    new_detector,
+    silero_vad::{VADInferenceInput, VADInferenceResult},
+  },
+  session::{EVENT_SPEECH_END, EVENT_SPEECH_START, VadSession, VadSessionEvent},
+};
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// `detect_speech_in_file` only writes segments under this dir in the app
+/// cache dir, wherever the webview asks for them.
+const SEGMENTS_DIR_NAME: &str = "vad-segments";
 
 #[derive(Default)]
-struct AppDataSileroVadProcessor {
-  silero_vad_processor: Option<crate::models::silero_vad::Processor>,
+struct AppDataVadDetector {
+  detector: Option<Arc<dyn VoiceActivityDetector>>,
+  /// Source the detector was loaded from, a different one loads it again.
+  source:   ModelSource,
 }
 
+/// The loaded detector, shared with whoever runs inference so the lock
+/// isn't held meanwhile.
+fn loaded_detector<R: Runtime>(
+  app: &tauri::AppHandle<R>
+) -> Result<Arc<dyn VoiceActivityDetector>, String> {
+  let data = app.state::<Mutex<AppDataVadDetector>>();
+  let data = data.lock().unwrap();
+  data
+    .detector
+    .clone()
+    .ok_or_else(|| "VAD model is not loaded".to_string())
+}
+
+/// A session is locked on its own while samples are pushed into it, so
+/// sessions don't wait for each other. `None` once it was closed.
+type SharedVadSession = Arc<Mutex<Option<VadSession>>>;
+
+#[derive(Default)]
+struct AppDataVadSessions {
+  sessions: HashMap<String, SharedVadSession>,
+}
+
+fn session<R: Runtime>(
+  app: &tauri::AppHandle<R>,
+  session_id: &str,
+) -> Result<SharedVadSession, String> {
+  let data = app.state::<Mutex<AppDataVadSessions>>();
+  let data = data
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner);
+  data
+    .sessions
+    .get(session_id)
+    .cloned()
+    .ok_or_else(|| format!("VAD session {session_id} does not exist"))
+}
+
+/// Load the detector of `backend`, Silero when not set, replacing the one
+/// loaded before. `source` tells where the model files come from.
 #[tauri::command]
 async fn load_ort_model_silero_vad<R: Runtime>(
   app: tauri::AppHandle<R>,
   window: tauri::WebviewWindow<R>,
+  backend: Option<VadBackend>,
+  source: Option<ModelSource>,
 ) -> Result<(), String> {
   info!("Loading models...");
+  let backend = backend.unwrap_or_default();
+  let source = source
+    .unwrap_or_default()
+    .resolve(&app)
+    .map_err(|e| e.to_string())?;
 
   {
-    let data = app.state::<Mutex<AppDataSileroVadProcessor>>();
+    let data = app.state::<Mutex<AppDataVadDetector>>();
     let data = data.lock().unwrap();
-    if data.silero_vad_processor.is_some() {
-      info!("Silero VAD model already loaded, skipping...");
+    if data
+      .detector
+      .as_ref()
+      .is_some_and(|detector| detector.backend() == backend && data.source == source)
+    {
+      info!("{backend:?} VAD model already loaded, skipping...");
       return Ok(());
     }
   }
 
-  match new_silero_vad_processor(window) {
-    Ok(p) => {
-      let data = app.state::<Mutex<AppDataSileroVadProcessor>>();
+  match new_detector(backend, window, source.clone()) {
+    Ok(detector) => {
+      let data = app.state::<Mutex<AppDataVadDetector>>();
       let mut data = data.lock().unwrap();
-      data.silero_vad_processor = Some(p);
-      info!("Silero VAD model loaded successfully");
+      data.detector = Some(detector);
+      data.source = source;
+      info!("{backend:?} VAD model loaded successfully");
     },
     Err(e) => {
-      let error_message = format!("Failed to load Silero VAD model: {}", e);
+      let error_message = format!("Failed to load {backend:?} VAD model: {e}");
       info!("{}", error_message);
       return Err(error_message);
     },
@@ -59,28 +139,187 @@ async fn ipc_audio_vad<R: Runtime>(
   app: tauri::AppHandle<R>,
   input_data: VADInferenceInput,
 ) -> Result<VADInferenceResult, String> {
-  let data = app.state::<Mutex<AppDataSileroVadProcessor>>();
-  let data = data.lock().unwrap();
+  let detector = loaded_detector(&app)?;
+  models::detect(detector.as_ref(), input_data).map_err(|e| e.to_string())
+}
 
-  if let Some(processor) = &data.silero_vad_processor {
-    processor
-      .inference(input_data)
-      .map_err(|e| e.to_string())
-  } else {
-    Err("Silero VAD model is not loaded".to_string())
+fn emit_session_events<R: Runtime>(
+  app: &tauri::AppHandle<R>,
+  window_label: &str,
+  events: Vec<VadSessionEvent>,
+) {
+  for event in events {
+    let emitted = match event {
+      VadSessionEvent::SpeechStart(payload) => {
+        app.emit_to(window_label, EVENT_SPEECH_START, payload)
+      },
+      VadSessionEvent::SpeechEnd(payload) => app.emit_to(window_label, EVENT_SPEECH_END, payload),
+    };
+    if let Err(err) = emitted {
+      error!("Failed to emit VAD session event: {err:?}");
+    }
   }
 }
 
+/// Start a streaming VAD session with the loaded detector, events of the
+/// session are emitted to the calling window.
+#[tauri::command]
+async fn create_session<R: Runtime>(
+  app: tauri::AppHandle<R>,
+  window: tauri::WebviewWindow<R>,
+  config: Option<VadSessionConfig>,
+) -> Result<String, String> {
+  let id = NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+  let id = format!("vad-session-{id}");
+  let session = VadSession::new(
+    id.clone(),
+    window.label().to_string(),
+    config.unwrap_or_default(),
+    loaded_detector(&app)?,
+  )
+  .map_err(|e| e.to_string())?;
+
+  let data = app.state::<Mutex<AppDataVadSessions>>();
+  data
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .sessions
+    .insert(id.clone(), Arc::new(Mutex::new(Some(session))));
+
+  info!("Created VAD session {id}");
+  Ok(id)
+}
+
+/// Feed recorded samples into a session, emitting `speech-start` and
+/// `speech-end` as speech is detected. Returns the speech probability of
+/// every window that was processed.
+#[tauri::command]
+async fn push_audio<R: Runtime>(
+  app: tauri::AppHandle<R>,
+  session_id: String,
+  samples: Vec<f32>,
+) -> Result<Vec<f32>, String> {
+  let session = session(&app, &session_id)?;
+
+  // Inference blocks, keep it off the async runtime
+  let (window_label, probabilities, events) = tauri::async_runtime::spawn_blocking(move || {
+    let mut session = session
+      .lock()
+      .map_err(|_| format!("VAD session {session_id} failed earlier and can't be used anymore"))?;
+    let Some(session) = session.as_mut() else {
+      return Err(format!("VAD session {session_id} was closed"));
+    };
+
+    let (probabilities, events) = session
+      .push(&samples)
+      .map_err(|e| e.to_string())?;
+    Ok((session.window_label.clone(), probabilities, events))
+  })
+  .await
+  .map_err(|e| e.to_string())??;
+  emit_session_events(&app, &window_label, events);
+
+  Ok(probabilities)
+}
+
+/// Close a session, ending the speech still going on.
+#[tauri::command]
+async fn close_session<R: Runtime>(
+  app: tauri::AppHandle<R>,
+  session_id: String,
+) -> Result<(), String> {
+  let session = {
+    let data = app.state::<Mutex<AppDataVadSessions>>();
+    let mut data = data
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner);
+    data.sessions.remove(&session_id)
+  };
+  let Some(session) = session else {
+    return Err(format!("VAD session {session_id} does not exist"));
+  };
+
+  // Waits for a push still running, later ones find the session closed
+  let session = session
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .take();
+  if let Some(session) = session {
+    let window_label = session.window_label.clone();
+    emit_session_events(&app, &window_label, session.close());
+  }
+
+  info!("Closed VAD session {session_id}");
+  Ok(())
+}
+
+/// Resolve the `output_dir` of `detect_speech_in_file` in the segments dir.
+/// It has to be relative and can't leave it.
+fn segments_dir<R: Runtime>(
+  app: &tauri::AppHandle<R>,
+  output_dir: &Path,
+) -> Result<PathBuf, String> {
+  let contained = output_dir
+    .components()
+    .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+  if !contained {
+    return Err(format!(
+      "Output dir must be relative to the segments dir: {}",
+      output_dir.display()
+    ));
+  }
+
+  let dir = app
+    .path()
+    .app_cache_dir()
+    .map_err(|e| e.to_string())?;
+  Ok(dir.join(SEGMENTS_DIR_NAME).join(output_dir))
+}
+
+/// Find the speech segments of an audio file (WAV, MP3, FLAC or OGG),
+/// optionally writing each of them as WAV to `output_dir`, a dir relative to
+/// `vad-segments` in the app cache dir. The sample rate and channels of
+/// `config` are taken from the file.
+#[tauri::command]
+async fn detect_speech_in_file<R: Runtime>(
+  app: tauri::AppHandle<R>,
+  path: PathBuf,
+  config: Option<VadSessionConfig>,
+  output_dir: Option<PathBuf>,
+) -> Result<Vec<SpeechSegment>, String> {
+  let detector = loaded_detector(&app)?;
+  let output_dir = output_dir
+    .map(|output_dir| segments_dir(&app, &output_dir))
+    .transpose()?;
+
+  tauri::async_runtime::spawn_blocking(move || {
+    file::detect_file(
+      detector,
+      &path,
+      config.unwrap_or_default(),
+      output_dir.as_deref(),
+    )
+  })
+  .await
+  .map_err(|e| e.to_string())?
+  .map_err(|e| e.to_string())
+}
+
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
   PluginBuilder::new("ipc-audio-vad-ort")
     .setup(|app, _| {
       info!("Initializing audio VAD plugin...");
-      app.manage(Mutex::new(AppDataSileroVadProcessor::default()));
+      app.manage(Mutex::new(AppDataVadDetector::default()));
+      app.manage(Mutex::new(AppDataVadSessions::default()));
       Ok(())
     })
     .invoke_handler(tauri::generate_handler![
       load_ort_model_silero_vad,
-      ipc_audio_vad
+      ipc_audio_vad,
+      create_session,
+      push_audio,
+      close_session,
+      detect_speech_in_file
     ])
     .build()
 }
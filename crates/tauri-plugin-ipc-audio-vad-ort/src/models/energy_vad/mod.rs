@@ -0,0 +1,110 @@
+use anyhow::{Result, anyhow};
+
+use crate::models::{VadBackend, VoiceActivityDetector};
+
+/// Length of a window in milliseconds.
+const WINDOW_MS: u32 = 30;
+
+/// Noise floor in dBFS a stream starts with, before it adapted to the input.
+const INITIAL_NOISE_FLOOR_DB: f32 = -50.0;
+
+/// Windows quieter than this are never speech, however quiet the room is.
+const SILENCE_DB: f32 = -60.0;
+
+/// Level above the noise floor at which the probability reaches 0.5.
+const SPEECH_SNR_DB: f32 = 12.0;
+
+/// How steeply the probability rises around `SPEECH_SNR_DB`.
+const SNR_SLOPE_DB: f32 = 3.0;
+
+/// Voiced speech rarely crosses zero this often, hiss and noise do.
+const NOISY_ZERO_CROSSING_RATE: f32 = 0.3;
+
+/// How fast the noise floor follows a quieter window.
+const FLOOR_FALL_RATE: f32 = 0.3;
+
+/// How fast the noise floor follows a louder window without speech.
+const FLOOR_RISE_RATE: f32 = 0.02;
+
+/// Model-free detector comparing the level of each window to an adaptive
+/// noise floor, damped for windows with a high zero-crossing rate.
+///
+/// Works at any sample rate without resampling. The state is the noise floor
+/// in dBFS.
+#[derive(Debug, Clone, Default)]
+pub struct Detector;
+
+impl VoiceActivityDetector for Detector {
+  fn backend(&self) -> VadBackend {
+    VadBackend::Energy
+  }
+
+  fn model_sample_rate(
+    &self,
+    sample_rate: u32,
+  ) -> u32 {
+    sample_rate
+  }
+
+  fn window_size(
+    &self,
+    sample_rate: u32,
+  ) -> Result<usize> {
+    match (sample_rate * WINDOW_MS / 1000) as usize {
+      0 => Err(anyhow!("Sample rate {sample_rate} Hz is too low")),
+      window_size => Ok(window_size),
+    }
+  }
+
+  fn initial_state(&self) -> Vec<f32> {
+    vec![INITIAL_NOISE_FLOOR_DB]
+  }
+
+  fn probability(
+    &self,
+    window: &[f32],
+    _sample_rate: u32,
+    state: &mut Vec<f32>,
+  ) -> Result<f32> {
+    let [floor] = state.as_mut_slice() else {
+      return Err(anyhow!("State must have 1 element, got {}", state.len()));
+    };
+    if window.is_empty() {
+      return Err(anyhow!("Window must not be empty"));
+    }
+
+    let energy = window
+      .iter()
+      .map(|sample| sample * sample)
+      .sum::<f32>()
+      / window.len() as f32;
+    let level = 10.0 * energy.max(1e-12).log10();
+
+    let crossings = window
+      .windows(2)
+      .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+      .count();
+    let zero_crossing_rate = crossings as f32 / (window.len() - 1).max(1) as f32;
+
+    let mut probability = if level < SILENCE_DB {
+      0.0
+    } else {
+      1.0 / (1.0 + (-(level - *floor - SPEECH_SNR_DB) / SNR_SLOPE_DB).exp())
+    };
+    if zero_crossing_rate > NOISY_ZERO_CROSSING_RATE {
+      probability *= 0.5;
+    }
+
+    // Follow the noise down quickly and up slowly, barely while speaking
+    let rate = if level < *floor {
+      FLOOR_FALL_RATE
+    } else if probability < 0.5 {
+      FLOOR_RISE_RATE
+    } else {
+      FLOOR_RISE_RATE * 0.1
+    };
+    *floor += (level - *floor) * rate;
+
+    Ok(probability)
+  }
+}
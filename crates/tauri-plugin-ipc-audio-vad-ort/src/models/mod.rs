@@ -1,10 +1,127 @@
+pub mod energy_vad;
 pub mod silero_vad;
+
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow};
+use ipc_audio_ort_helpers::{audio, model_source::ModelSource};
 use log::info;
+use serde::{Deserialize, Serialize};
 use tauri::Runtime;
 
+use crate::models::silero_vad::{VADInferenceInput, VADInferenceResult};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VadBackend {
+  /// Silero VAD on ONNX Runtime, downloaded from Hugging Face on first use.
+  #[default]
+  Silero,
+  /// Energy and zero-crossing rate, needs no model and is deterministic.
+  Energy,
+}
+
+/// Speech detection over fixed-size windows of mono audio.
+///
+/// Detectors keep no state of their own, whatever they carry from window to
+/// window lives in `state`, so one detector can serve many streams.
+pub trait VoiceActivityDetector: Send + Sync {
+  fn backend(&self) -> VadBackend;
+
+  /// Sample rate audio recorded at `sample_rate` is resampled to.
+  fn model_sample_rate(
+    &self,
+    sample_rate: u32,
+  ) -> u32;
+
+  /// Number of samples per window at a rate returned by `model_sample_rate`.
+  fn window_size(
+    &self,
+    sample_rate: u32,
+  ) -> Result<usize>;
+
+  /// State to start a stream with.
+  fn initial_state(&self) -> Vec<f32>;
+
+  /// Speech probability of one window, updating `state` in place.
+  fn probability(
+    &self,
+    window: &[f32],
+    sample_rate: u32,
+    state: &mut Vec<f32>,
+  ) -> Result<f32>;
+}
+
 pub fn new_silero_vad_processor<R: Runtime>(
-  window: tauri::WebviewWindow<R>
+  window: tauri::WebviewWindow<R>,
+  source: ModelSource,
 ) -> anyhow::Result<silero_vad::Processor> {
   info!("Loading VAD model");
-  silero_vad::Processor::new(window)
+  silero_vad::Processor::new(window, source)
+}
+
+/// Create the detector of `backend`, `source` only applies to backends with
+/// a model.
+pub fn new_detector<R: Runtime>(
+  backend: VadBackend,
+  window: tauri::WebviewWindow<R>,
+  source: ModelSource,
+) -> Result<Arc<dyn VoiceActivityDetector>> {
+  match backend {
+    VadBackend::Silero => Ok(Arc::new(new_silero_vad_processor(window, source)?)),
+    VadBackend::Energy => Ok(Arc::new(energy_vad::Detector)),
+  }
+}
+
+/// Detection over audio of any sample rate and channel count.
+///
+/// The input is downmixed, resampled to a rate the detector supports and cut
+/// into windows. It has to fill whole windows, padding the last one would
+/// skew its probability and the state carried into the next call. Streams
+/// cut at arbitrary lengths should go through a session instead, see
+/// [`crate::session::VadSession`]. The state is carried from window to
+/// window, and one probability is returned per window.
+pub fn detect(
+  detector: &dyn VoiceActivityDetector,
+  input_data: VADInferenceInput,
+) -> Result<VADInferenceResult> {
+  let mut state = input_data.state;
+  if state.is_empty() {
+    state = detector.initial_state();
+  }
+  let state_size = detector.initial_state().len();
+  if state.len() != state_size {
+    return Err(anyhow!(
+      "State of the {:?} backend must have {state_size} elements, got {}",
+      detector.backend(),
+      state.len()
+    ));
+  }
+  let sample_rate = u32::try_from(input_data.sr)
+    .ok()
+    .filter(|sample_rate| *sample_rate > 0)
+    .ok_or_else(|| anyhow!("Invalid sample rate {} Hz", input_data.sr))?;
+  if input_data.input.is_empty() {
+    return Err(anyhow!("Input must not be empty"));
+  }
+
+  let samples = audio::downmix(&input_data.input, input_data.channels.unwrap_or(1))?;
+  let model_sample_rate = detector.model_sample_rate(sample_rate);
+  let samples = audio::resample(&samples, sample_rate, model_sample_rate)?;
+
+  let window_size = detector.window_size(model_sample_rate)?;
+  if samples.len() % window_size != 0 {
+    return Err(anyhow!(
+      "Got {} samples at {model_sample_rate} Hz, which is not a whole number of {window_size} sample windows of the {:?} backend. Use a session (`create_session` and `push_audio`) for audio of any length",
+      samples.len(),
+      detector.backend()
+    ));
+  }
+
+  let output = samples
+    .chunks_exact(window_size)
+    .map(|window| detector.probability(window, model_sample_rate, &mut state))
+    .collect::<Result<Vec<_>>>()?;
+
+  Ok(VADInferenceResult { output, state })
 }
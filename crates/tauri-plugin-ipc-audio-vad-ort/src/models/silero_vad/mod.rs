@@ -1,7 +1,7 @@
 use std::{path::PathBuf, sync::Arc};
 
 use anyhow::Result;
-use hf_hub::Repo;
+use ipc_audio_ort_helpers::model_source::{ModelFiles, ModelPin, ModelSource};
 use log::info;
 use ort::{
   execution_providers::{
@@ -17,56 +17,74 @@ use ort::{
 use serde::{Deserialize, Serialize};
 use tauri::Runtime;
 
-use crate::helpers::huggingface::create_progress_emitter;
+use crate::models::{VadBackend, VoiceActivityDetector};
+
+/// Commit of `onnx-community/silero-vad` the model is loaded from and the
+/// digest of `onnx/model.onnx` at it. Nothing is pinned yet, so the model only
+/// loads with its digest in `ModelSource::sha256`, the load error names it.
+const MODEL_PIN: ModelPin = ModelPin {
+  revision: "main",
+  sha256:   &[],
+};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct VADInferenceResult {
-  pub output: Vec<f32>, // Speech probability output
+  pub output: Vec<f32>, // Speech probability output, one per window
   pub state:  Vec<f32>, // Updated state for next inference
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct VADInferenceInput {
-  pub input: Vec<f32>, // Audio input buffer
-  pub sr:    i64,      // Sample rate
-  pub state: Vec<f32>, // Current state
+  pub input:    Vec<f32>, // Audio input buffer, interleaved when there are several channels
+  pub sr:       i64,      // Sample rate
+  pub state:    Vec<f32>, // Current state, empty to start from the initial one
+  #[serde(default)]
+  pub channels: Option<u16>, // Channel count, mono when not set
+}
+
+/// Length of the LSTM state carried between inferences, shaped (2, 1, 128).
+pub const STATE_SIZE: usize = 2 * 128;
+
+/// Sample rate input at rates the model doesn't support is resampled to.
+pub const DEFAULT_SAMPLE_RATE: u32 = 16000;
+
+/// Sample rate audio recorded at `sample_rate` is run through the model at.
+pub fn model_sample_rate(sample_rate: u32) -> u32 {
+  match sample_rate {
+    8000 | 16000 => sample_rate,
+    _ => DEFAULT_SAMPLE_RATE,
+  }
 }
 
+/// Number of samples the model takes per inference at a sample rate.
+pub fn window_size(sample_rate: i64) -> Result<usize> {
+  match sample_rate {
+    8000 => Ok(256),
+    16000 => Ok(512),
+    _ => Err(anyhow::anyhow!(
+      "Silero VAD runs at 8000 or 16000 Hz, got {sample_rate} Hz"
+    )),
+  }
+}
+
+#[derive(Clone)]
 pub struct Processor {
   session: Arc<Mutex<Session>>,
 }
 
 impl Processor {
-  pub fn new<R: Runtime>(window: tauri::WebviewWindow<R>) -> Result<Self> {
-    let model_id = "onnx-community/silero-vad";
-    let revision = "main";
-
-    let cache_api = hf_hub::Cache::from_env();
-    let cache_repo = cache_api.repo(Repo::with_revision(
-      model_id.into(),
-      hf_hub::RepoType::Model,
-      revision.into(),
-    ));
-
-    let api = hf_hub::api::sync::ApiBuilder::new().build()?;
-    let repo = api.repo(hf_hub::Repo::with_revision(
-      model_id.into(),
-      hf_hub::RepoType::Model,
-      revision.into(),
-    ));
-
-    let model_path_sub_name = "onnx/model.onnx";
-    let model_path = match cache_repo.get(model_path_sub_name) {
-      Some(path) => path,
-      None => repo.download_with_progress(
-        model_path_sub_name,
-        create_progress_emitter(
-          window.clone(),
-          "tauri-plugins:tauri-plugin-ipc-audio-vad-ort:load-model-silero-vad-progress",
-          model_path_sub_name.to_string(),
-        ),
-      )?,
-    };
+  pub fn new<R: Runtime>(
+    window: tauri::WebviewWindow<R>,
+    source: ModelSource,
+  ) -> Result<Self> {
+    let files = ModelFiles::new(
+      "onnx-community/silero-vad",
+      MODEL_PIN,
+      source,
+      window,
+      "tauri-plugins:tauri-plugin-ipc-audio-vad-ort:load-model-silero-vad-progress",
+    )?;
+    let model_path = files.get("onnx/model.onnx")?;
 
     let session = Self::create_optimized_session(model_path.clone())?;
 
@@ -105,12 +123,20 @@ impl Processor {
     input_data: VADInferenceInput,
   ) -> Result<VADInferenceResult> {
     // Validate input dimensions
-    if input_data.state.len() != 2 * 1 * 128 {
+    if input_data.state.len() != STATE_SIZE {
       return Err(anyhow::anyhow!(
         "State must have 256 elements (2*1*128), got {}",
         input_data.state.len()
       ));
     }
+    let window_size = window_size(input_data.sr)?;
+    if input_data.input.len() != window_size {
+      return Err(anyhow::anyhow!(
+        "Input at {} Hz must have {window_size} samples, got {}",
+        input_data.sr,
+        input_data.input.len()
+      ));
+    }
 
     // Create input tensors for the ONNX model
     let inputs = vec![
@@ -147,3 +173,48 @@ impl Processor {
     })
   }
 }
+
+impl VoiceActivityDetector for Processor {
+  fn backend(&self) -> VadBackend {
+    VadBackend::Silero
+  }
+
+  fn model_sample_rate(
+    &self,
+    sample_rate: u32,
+  ) -> u32 {
+    model_sample_rate(sample_rate)
+  }
+
+  fn window_size(
+    &self,
+    sample_rate: u32,
+  ) -> Result<usize> {
+    window_size(i64::from(sample_rate))
+  }
+
+  fn initial_state(&self) -> Vec<f32> {
+    vec![0.0; STATE_SIZE]
+  }
+
+  fn probability(
+    &self,
+    window: &[f32],
+    sample_rate: u32,
+    state: &mut Vec<f32>,
+  ) -> Result<f32> {
+    let result = self.inference(VADInferenceInput {
+      input:    window.to_vec(),
+      sr:       i64::from(sample_rate),
+      state:    state.clone(),
+      channels: None,
+    })?;
+    *state = result.state;
+
+    result
+      .output
+      .first()
+      .copied()
+      .ok_or_else(|| anyhow::anyhow!("Silero VAD returned no probability"))
+  }
+}
@@ -0,0 +1,561 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use anyhow::{Result, anyhow};
+use ipc_audio_ort_helpers::audio::{self, StreamResampler};
+use serde::{Deserialize, Serialize};
+
+use crate::models::VoiceActivityDetector;
+
+pub const EVENT_SPEECH_START: &str = "tauri-plugins:tauri-plugin-ipc-audio-vad-ort:speech-start";
+pub const EVENT_SPEECH_END: &str = "tauri-plugins:tauri-plugin-ipc-audio-vad-ort:speech-end";
+
+/// Tuning of a streaming VAD session, durations are in milliseconds.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VadSessionConfig {
+  /// Sample rate of the pushed audio. Rates other than 8000 and 16000 Hz
+  /// are resampled to 16000 Hz.
+  pub sample_rate:             u32,
+  /// Channel count of the pushed audio, interleaved. Downmixed to mono.
+  pub channels:                u16,
+  /// Probability at which speech starts.
+  pub positive_threshold:      f32,
+  /// Probability below which speech counts as silence again.
+  pub negative_threshold:      f32,
+  /// Shorter speech is dropped without any event.
+  pub min_speech_duration_ms:  u32,
+  /// Silence needed before speech ends. Must be above 0.
+  pub min_silence_duration_ms: u32,
+  /// Audio kept before the start of speech.
+  pub pre_padding_ms:          u32,
+  /// Audio kept after the end of speech.
+  pub post_padding_ms:         u32,
+  /// Speech is cut into segments of at most this length. Must be above 0.
+  pub max_speech_duration_ms:  u32,
+}
+
+impl Default for VadSessionConfig {
+  fn default() -> Self {
+    Self {
+      sample_rate:             16000,
+      channels:                1,
+      positive_threshold:      0.3,
+      negative_threshold:      0.1,
+      min_speech_duration_ms:  250,
+      min_silence_duration_ms: 400,
+      pre_padding_ms:          80,
+      post_padding_ms:         80,
+      max_speech_duration_ms:  30000,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeechStartPayload {
+  pub session_id:  String,
+  /// Sample offset the speech starts at, including the pre-padding.
+  pub start:       u64,
+  /// Sample rate offsets are counted at, the one the model runs at.
+  pub sample_rate: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeechEndPayload {
+  pub session_id:  String,
+  pub start:       u64,
+  /// Sample offset the speech ends at, including the post-padding.
+  pub end:         u64,
+  /// Sample rate of the offsets and of `audio`.
+  pub sample_rate: u32,
+  /// The mono samples from `start` to `end`.
+  pub audio:       Vec<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub enum VadSessionEvent {
+  SpeechStart(SpeechStartPayload),
+  SpeechEnd(SpeechEndPayload),
+}
+
+struct Speech {
+  /// Offset of `audio[0]`.
+  start:         u64,
+  /// Offset of the first window detected as speech.
+  detected:      u64,
+  audio:         Vec<f32>,
+  /// Set once the speech lasted `min_speech_duration_ms`, when
+  /// `speech-start` is emitted.
+  confirmed:     bool,
+  silence_since: Option<u64>,
+}
+
+/// Streaming VAD over the audio pushed by one webview, keeping the state of
+/// the detector and the speech buffered so far between pushes.
+pub struct VadSession {
+  pub id:            String,
+  /// Label of the window events are emitted to.
+  pub window_label:  String,
+  config:            VadSessionConfig,
+  /// The detector loaded when the session was created, loading another
+  /// backend doesn't affect running sessions.
+  detector:          Arc<dyn VoiceActivityDetector>,
+  /// Rate the audio is resampled to, offsets count samples at this rate.
+  model_sample_rate: u32,
+  /// Set when the pushed audio isn't at `model_sample_rate`.
+  resampler:         Option<StreamResampler>,
+  window_size:       usize,
+  state:             Vec<f32>,
+  /// Samples not filling a whole window yet.
+  pending:           Vec<f32>,
+  /// Samples processed so far.
+  offset:            u64,
+  pre_roll:          VecDeque<f32>,
+  speech:            Option<Speech>,
+}
+
+impl VadSession {
+  pub fn new(
+    id: String,
+    window_label: String,
+    config: VadSessionConfig,
+    detector: Arc<dyn VoiceActivityDetector>,
+  ) -> Result<Self> {
+    if config.negative_threshold > config.positive_threshold {
+      return Err(anyhow!(
+        "negativeThreshold ({}) must not be above positiveThreshold ({})",
+        config.negative_threshold,
+        config.positive_threshold
+      ));
+    }
+    if config.sample_rate == 0 {
+      return Err(anyhow!("sampleRate must be above 0 Hz"));
+    }
+    if config.channels == 0 {
+      return Err(anyhow!("channels must be at least 1"));
+    }
+    if config.min_silence_duration_ms == 0 {
+      return Err(anyhow!("minSilenceDurationMs must be above 0 ms"));
+    }
+    if config.max_speech_duration_ms == 0 {
+      return Err(anyhow!("maxSpeechDurationMs must be above 0 ms"));
+    }
+
+    let model_sample_rate = detector.model_sample_rate(config.sample_rate);
+    let resampler = if config.sample_rate == model_sample_rate {
+      None
+    } else {
+      Some(StreamResampler::new(config.sample_rate, model_sample_rate)?)
+    };
+
+    Ok(Self {
+      id,
+      window_label,
+      model_sample_rate,
+      resampler,
+      window_size: detector.window_size(model_sample_rate)?,
+      state: detector.initial_state(),
+      detector,
+      config,
+      pending: Vec::new(),
+      offset: 0,
+      pre_roll: VecDeque::new(),
+      speech: None,
+    })
+  }
+
+  /// Rate offsets and emitted audio are at.
+  pub fn model_sample_rate(&self) -> u32 {
+    self.model_sample_rate
+  }
+
+  /// Samples per window, `push` returns one probability per window.
+  pub fn window_size(&self) -> usize {
+    self.window_size
+  }
+
+  /// Number of samples at the model's rate a duration spans.
+  fn samples(
+    &self,
+    duration_ms: u32,
+  ) -> u64 {
+    u64::from(duration_ms) * u64::from(self.model_sample_rate) / 1000
+  }
+
+  /// Run VAD over newly recorded samples, returning the speech probability
+  /// of every complete window and the events they caused.
+  pub fn push(
+    &mut self,
+    samples: &[f32],
+  ) -> Result<(Vec<f32>, Vec<VadSessionEvent>)> {
+    let samples = audio::downmix(samples, self.config.channels)?;
+    match &mut self.resampler {
+      Some(resampler) => {
+        let resampled = resampler.process(&samples)?;
+        self.pending.extend(resampled);
+      },
+      None => self.pending.extend(samples),
+    }
+
+    let mut probabilities = Vec::new();
+    let mut events = Vec::new();
+    let windows = self.pending.len() / self.window_size;
+    for index in 0..windows {
+      let window = &self.pending[index * self.window_size..(index + 1) * self.window_size];
+      let probability =
+        self
+          .detector
+          .probability(window, self.model_sample_rate, &mut self.state)?;
+      probabilities.push(probability);
+
+      let window = window.to_vec();
+      self.process(&window, probability, &mut events);
+    }
+    self.pending.drain(..windows * self.window_size);
+
+    Ok((probabilities, events))
+  }
+
+  fn process(
+    &mut self,
+    window: &[f32],
+    probability: f32,
+    events: &mut Vec<VadSessionEvent>,
+  ) {
+    let window_start = self.offset;
+    let window_end = window_start + window.len() as u64;
+    self.offset = window_end;
+
+    let Some(speech) = &mut self.speech else {
+      if probability >= self.config.positive_threshold {
+        let mut audio: Vec<f32> = self.pre_roll.drain(..).collect();
+        let start = window_start - audio.len() as u64;
+        audio.extend_from_slice(window);
+        self.speech = Some(Speech {
+          start,
+          detected: window_start,
+          audio,
+          confirmed: false,
+          silence_since: None,
+        });
+        self.confirm(events);
+      } else {
+        self.buffer_pre_roll(window);
+      }
+      return;
+    };
+
+    speech.audio.extend_from_slice(window);
+    if probability < self.config.negative_threshold {
+      speech.silence_since.get_or_insert(window_start);
+    } else {
+      speech.silence_since = None;
+    }
+    self.confirm(events);
+
+    let Some(speech) = &self.speech else {
+      return;
+    };
+    let min_silence = self.samples(self.config.min_silence_duration_ms);
+    let max_speech = self.samples(self.config.max_speech_duration_ms);
+    if let Some(since) = speech.silence_since {
+      if window_end - since >= min_silence {
+        let end = since + self.samples(self.config.post_padding_ms);
+        self.end(end, events);
+        return;
+      }
+    }
+    if window_end - speech.start >= max_speech {
+      self.end(window_end, events);
+    }
+  }
+
+  /// Emit `speech-start` once the speech lasted long enough.
+  fn confirm(
+    &mut self,
+    events: &mut Vec<VadSessionEvent>,
+  ) {
+    let min_speech = self.samples(self.config.min_speech_duration_ms);
+    let Some(speech) = &mut self.speech else {
+      return;
+    };
+    if speech.confirmed {
+      return;
+    }
+
+    let spoken = speech.silence_since.unwrap_or(self.offset) - speech.detected;
+    if spoken >= min_speech {
+      speech.confirmed = true;
+      events.push(VadSessionEvent::SpeechStart(SpeechStartPayload {
+        session_id:  self.id.clone(),
+        start:       speech.start,
+        sample_rate: self.model_sample_rate,
+      }));
+    }
+  }
+
+  /// Finish the current speech at `end`, keeping what follows as pre-roll
+  /// of the next one.
+  fn end(
+    &mut self,
+    end: u64,
+    events: &mut Vec<VadSessionEvent>,
+  ) {
+    let Some(mut speech) = self.speech.take() else {
+      return;
+    };
+
+    let end = end.clamp(speech.start, self.offset);
+    let rest = speech
+      .audio
+      .split_off((end - speech.start) as usize);
+
+    if speech.confirmed {
+      events.push(VadSessionEvent::SpeechEnd(SpeechEndPayload {
+        session_id: self.id.clone(),
+        start: speech.start,
+        end,
+        sample_rate: self.model_sample_rate,
+        audio: speech.audio,
+      }));
+    }
+
+    self.buffer_pre_roll(&rest);
+  }
+
+  fn buffer_pre_roll(
+    &mut self,
+    samples: &[f32],
+  ) {
+    let pre_padding = self.samples(self.config.pre_padding_ms) as usize;
+    self.pre_roll.extend(samples);
+    let excess = self.pre_roll.len().saturating_sub(pre_padding);
+    self.pre_roll.drain(..excess);
+  }
+
+  /// End the speech still going on, including samples that didn't fill a
+  /// whole window.
+  pub fn close(mut self) -> Vec<VadSessionEvent> {
+    let mut events = Vec::new();
+    if let Some(resampler) = &mut self.resampler {
+      match resampler.flush() {
+        Ok(resampled) => self.pending.extend(resampled),
+        Err(err) => log::warn!(
+          "Failed to flush the resampler of VAD session {}: {err}",
+          self.id
+        ),
+      }
+    }
+    if let Some(speech) = &mut self.speech {
+      speech.audio.append(&mut self.pending);
+      self.offset = speech.start + speech.audio.len() as u64;
+      let end = self.offset;
+      self.end(end, &mut events);
+    }
+
+    events
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use std::{f32::consts::PI, sync::Arc};
+
+  use super::{VadSession, VadSessionConfig, VadSessionEvent};
+  use crate::models::energy_vad::Detector;
+
+  pub(crate) const SAMPLE_RATE: u32 = 16000;
+
+  /// Samples per millisecond at `SAMPLE_RATE`.
+  pub(crate) const MS: u64 = 16;
+
+  pub(crate) fn silence(ms: u32) -> Vec<f32> {
+    vec![0.0; (u64::from(ms) * MS) as usize]
+  }
+
+  /// A 200 Hz tone, loud and with few zero crossings, detected as speech.
+  pub(crate) fn tone(ms: u32) -> Vec<f32> {
+    (0..u64::from(ms) * MS)
+      .map(|i| 0.3 * (2.0 * PI * 200.0 * i as f32 / SAMPLE_RATE as f32).sin())
+      .collect()
+  }
+
+  pub(crate) fn config() -> VadSessionConfig {
+    VadSessionConfig {
+      pre_padding_ms: 0,
+      post_padding_ms: 0,
+      ..Default::default()
+    }
+  }
+
+  /// Push all of `samples` in chunks of `chunk_size` and close the session.
+  fn run(
+    config: VadSessionConfig,
+    samples: &[f32],
+    chunk_size: usize,
+  ) -> Vec<VadSessionEvent> {
+    let mut session = VadSession::new(
+      "test".to_string(),
+      String::new(),
+      config,
+      Arc::new(Detector),
+    )
+    .unwrap();
+    let mut events = Vec::new();
+    for chunk in samples.chunks(chunk_size) {
+      events.extend(session.push(chunk).unwrap().1);
+    }
+    events.extend(session.close());
+    events
+  }
+
+  /// `(start, end)` of every `speech-end`, checking each follows its
+  /// `speech-start`.
+  fn segments(events: &[VadSessionEvent]) -> Vec<(u64, u64)> {
+    let mut started = None;
+    let mut segments = Vec::new();
+    for event in events {
+      match event {
+        VadSessionEvent::SpeechStart(payload) => {
+          assert_eq!(started, None, "speech started twice");
+          started = Some(payload.start);
+        },
+        VadSessionEvent::SpeechEnd(payload) => {
+          assert_eq!(started.take(), Some(payload.start));
+          assert_eq!(payload.audio.len() as u64, payload.end - payload.start);
+          segments.push((payload.start, payload.end));
+        },
+      }
+    }
+    assert_eq!(started, None, "speech never ended");
+    segments
+  }
+
+  #[test]
+  fn detects_start_and_end_of_speech() {
+    let samples = [silence(600), tone(900), silence(900)].concat();
+
+    // Chunks not aligned to windows must not change the result
+    for chunk_size in [samples.len(), 480, 1000] {
+      let events = run(config(), &samples, chunk_size);
+      assert_eq!(segments(&events), [(600 * MS, 1500 * MS)]);
+    }
+  }
+
+  #[test]
+  fn drops_speech_shorter_than_min_speech_duration() {
+    let samples = [silence(600), tone(150), silence(900)].concat();
+    assert!(run(config(), &samples, 480).is_empty());
+
+    let config = VadSessionConfig {
+      min_speech_duration_ms: 120,
+      ..config()
+    };
+    assert_eq!(
+      segments(&run(config, &samples, 480)),
+      [(600 * MS, 750 * MS)]
+    );
+  }
+
+  #[test]
+  fn bridges_pauses_shorter_than_min_silence_duration() {
+    let samples = [
+      silence(600),
+      tone(600),
+      silence(240),
+      tone(600),
+      silence(900),
+    ]
+    .concat();
+    assert_eq!(
+      segments(&run(config(), &samples, 480)),
+      [(600 * MS, 2040 * MS)]
+    );
+
+    let config = VadSessionConfig {
+      min_silence_duration_ms: 210,
+      ..config()
+    };
+    assert_eq!(
+      segments(&run(config, &samples, 480)),
+      [(600 * MS, 1200 * MS), (1440 * MS, 2040 * MS)]
+    );
+  }
+
+  #[test]
+  fn splits_speech_longer_than_max_speech_duration() {
+    let samples = [silence(600), tone(3000), silence(900)].concat();
+    let config = VadSessionConfig {
+      max_speech_duration_ms: 1200,
+      ..config()
+    };
+
+    assert_eq!(
+      segments(&run(config, &samples, 480)),
+      [
+        (600 * MS, 1800 * MS),
+        (1800 * MS, 3000 * MS),
+        (3000 * MS, 3600 * MS)
+      ]
+    );
+  }
+
+  #[test]
+  fn pads_speech_before_and_after() {
+    let samples = [silence(600), tone(900), silence(900)].concat();
+    let config = VadSessionConfig {
+      pre_padding_ms: 90,
+      post_padding_ms: 120,
+      ..config()
+    };
+
+    let events = run(config, &samples, 480);
+    assert_eq!(segments(&events), [(510 * MS, 1620 * MS)]);
+
+    let Some(VadSessionEvent::SpeechEnd(payload)) = events.last() else {
+      panic!("no speech-end");
+    };
+    let pre_padding = (90 * MS) as usize;
+    assert!(
+      payload.audio[..pre_padding]
+        .iter()
+        .all(|sample| *sample == 0.0)
+    );
+    assert_eq!(
+      &payload.audio[pre_padding..pre_padding + 10],
+      &tone(1)[..10]
+    );
+  }
+
+  #[test]
+  fn ends_speech_still_going_on_when_closed() {
+    let samples = [silence(600), tone(900)].concat();
+    assert_eq!(
+      segments(&run(config(), &samples, 1000)),
+      [(600 * MS, 1500 * MS)]
+    );
+  }
+
+  #[test]
+  fn rejects_zero_silence_and_speech_durations() {
+    for config in [
+      VadSessionConfig {
+        min_silence_duration_ms: 0,
+        ..config()
+      },
+      VadSessionConfig {
+        max_speech_duration_ms: 0,
+        ..config()
+      },
+    ] {
+      let session = VadSession::new(
+        "test".to_string(),
+        String::new(),
+        config,
+        Arc::new(Detector),
+      );
+      assert!(session.is_err());
+    }
+  }
+}
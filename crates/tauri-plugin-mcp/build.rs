@@ -1,8 +1,37 @@
 const COMMANDS: &[&str] = &[
   "connect_server",
   "disconnect_server",
+  "list_servers",
+  "get_server_status",
   "list_tools",
+  "list_all_tools",
   "call_tool",
+  "cancel_tool_call",
+  "query_audit_log",
+  "get_server_logs",
+  "set_log_level",
+  "list_resources",
+  "list_resource_templates",
+  "read_resource",
+  "subscribe_resource",
+  "unsubscribe_resource",
+  "list_prompts",
+  "get_prompt",
+  "complete",
+  "get_config",
+  "set_config",
+  "reload_config",
+  "get_roots",
+  "set_roots",
+  "get_policy",
+  "set_policy",
+  "respond_consent",
+  "respond_sampling",
+  "reject_sampling",
+  "respond_elicitation",
+  "list_secrets",
+  "set_secret",
+  "delete_secret",
 ];
 
 fn main() {
@@ -0,0 +1,247 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  time::{Duration, SystemTime},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{info, warn};
+use rmcp::model::{CallToolResult, Content, Meta, RawContent, ResourceContents};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::McpError;
+
+/// Directory under the app cache dir decoded content blocks are written to.
+pub const ASSETS_DIR_NAME: &str = "mcp-assets";
+
+/// Assets older than this are removed when the plugin starts.
+const ASSET_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpAssetKind {
+  Image,
+  Audio,
+  /// An embedded binary resource, text resources are passed on as is.
+  Resource,
+}
+
+/// A content block of a tool result that was written to the assets dir.
+///
+/// Serialized as `{ type: 'asset', kind: 'image', path: '...', ... }`, the
+/// webview loads it with `convertFileSrc(path)`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename = "asset", rename_all = "camelCase")]
+pub struct McpAsset {
+  pub kind:      McpAssetKind,
+  pub mime_type: Option<String>,
+  /// URI of an embedded resource.
+  pub uri:       Option<String>,
+  pub path:      PathBuf,
+  /// Size of the decoded content in bytes.
+  pub size:      u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum McpToolContent {
+  Asset(McpAsset),
+  /// Text and resource links, passed on as sent by the server.
+  Content(Content),
+}
+
+/// `CallToolResult` with the binary content blocks replaced by assets, so
+/// large base64 strings don't have to pass through IPC.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolResult {
+  pub content:            Vec<McpToolContent>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub structured_content: Option<Value>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_error:           Option<bool>,
+  #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+  pub meta:               Option<Meta>,
+}
+
+pub fn assets_dir<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<PathBuf> {
+  Ok(app.path().app_cache_dir()?.join(ASSETS_DIR_NAME))
+}
+
+/// Create the assets dir, drop stale assets and let the webview read the
+/// assets in it through the asset protocol.
+pub(crate) fn setup<R: Runtime>(app: &AppHandle<R>) {
+  let dir = match assets_dir(app) {
+    Ok(dir) => dir,
+    Err(err) => {
+      warn!("Failed to resolve the MCP assets dir: {err}");
+      return;
+    },
+  };
+
+  if let Err(err) = fs::create_dir_all(&dir) {
+    warn!("Failed to create {}: {err}", dir.display());
+    return;
+  }
+  prune(&dir);
+
+  if let Err(err) = app
+    .asset_protocol_scope()
+    .allow_directory(&dir, false)
+  {
+    warn!(
+      "Failed to allow {} for the asset protocol: {err}",
+      dir.display()
+    );
+  }
+}
+
+fn prune(dir: &Path) {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return;
+  };
+
+  let now = SystemTime::now();
+  let mut removed = 0;
+  for entry in entries.flatten() {
+    let stale = entry
+      .metadata()
+      .and_then(|metadata| metadata.modified())
+      .is_ok_and(|modified| {
+        now
+          .duration_since(modified)
+          .is_ok_and(|age| age > ASSET_MAX_AGE)
+      });
+    let is_file = entry
+      .file_type()
+      .is_ok_and(|file_type| file_type.is_file());
+    if is_file && stale && fs::remove_file(entry.path()).is_ok() {
+      removed += 1;
+    }
+  }
+
+  if removed > 0 {
+    info!("Removed {removed} stale MCP assets");
+  }
+}
+
+/// Extension of an asset, detected from its content rather than the MIME
+/// type the server claims, as that's what the asset protocol serves it as.
+/// Undetected content is written as `bin` and served as
+/// `application/octet-stream`.
+///
+/// `None` for HTML, XML and scripts, the webview must never load those.
+fn extension(data: &[u8]) -> Option<&'static str> {
+  match infer::get(data) {
+    None => Some("bin"),
+    Some(detected) if detected.matcher_type() == infer::MatcherType::Text => None,
+    Some(detected) => Some(detected.extension()),
+  }
+}
+
+/// Write decoded content named by its hash, so the same image returned
+/// twice is only stored once.
+fn write(
+  dir: &Path,
+  data: &[u8],
+  extension: &str,
+) -> anyhow::Result<PathBuf> {
+  let hash = Sha256::digest(data)
+    .iter()
+    .map(|byte| format!("{byte:02x}"))
+    .collect::<String>();
+  let path = dir.join(format!("{hash}.{extension}"));
+  if !path.exists() {
+    fs::create_dir_all(dir)?;
+    fs::write(&path, data)?;
+  }
+
+  Ok(path)
+}
+
+fn asset(
+  dir: &Path,
+  content: &Content,
+) -> anyhow::Result<Option<McpAsset>> {
+  let (kind, mime_type, uri, data) = match &content.raw {
+    RawContent::Image(image) => (
+      McpAssetKind::Image,
+      Some(image.mime_type.clone()),
+      None,
+      STANDARD.decode(&image.data)?,
+    ),
+    RawContent::Audio(audio) => (
+      McpAssetKind::Audio,
+      Some(audio.mime_type.clone()),
+      None,
+      STANDARD.decode(&audio.data)?,
+    ),
+    RawContent::Resource(resource) => match &resource.resource {
+      // Readable as it is, and never served as a document
+      ResourceContents::TextResourceContents { .. } => return Ok(None),
+      ResourceContents::BlobResourceContents {
+        uri,
+        mime_type,
+        blob,
+        ..
+      } => (
+        McpAssetKind::Resource,
+        mime_type.clone(),
+        Some(uri.clone()),
+        STANDARD.decode(blob)?,
+      ),
+    },
+    RawContent::Text(_) | RawContent::ResourceLink(_) => return Ok(None),
+  };
+
+  let Some(extension) = extension(&data) else {
+    warn!("Not writing MCP content detected as a document or script to the assets dir");
+    return Ok(None);
+  };
+
+  let path = write(dir, &data, extension)?;
+  Ok(Some(McpAsset {
+    kind,
+    mime_type,
+    uri,
+    path,
+    size: data.len() as u64,
+  }))
+}
+
+/// Replace the images, audio and embedded binary resources of a tool result
+/// with assets. Blocks that can't be written, or are HTML, XML or scripts,
+/// are passed on unchanged.
+pub(crate) async fn convert<R: Runtime>(
+  app: &AppHandle<R>,
+  result: CallToolResult,
+) -> Result<McpToolResult, McpError> {
+  let dir = assets_dir(app).map_err(|err| McpError::Other(err.to_string()))?;
+
+  tauri::async_runtime::spawn_blocking(move || {
+    let content = result
+      .content
+      .into_iter()
+      .map(|content| match asset(&dir, &content) {
+        Ok(Some(asset)) => McpToolContent::Asset(asset),
+        Ok(None) => McpToolContent::Content(content),
+        Err(err) => {
+          warn!("Failed to write MCP content to the assets dir: {err}");
+          McpToolContent::Content(content)
+        },
+      })
+      .collect();
+
+    McpToolResult {
+      content,
+      structured_content: result.structured_content,
+      is_error: result.is_error,
+      meta: result.meta,
+    }
+  })
+  .await
+  .map_err(|err| McpError::Other(err.to_string()))
+}
@@ -0,0 +1,345 @@
+use std::{
+  fs::{self, File, OpenOptions},
+  io::{BufRead, BufReader, Write},
+  path::{Path, PathBuf},
+  sync::Mutex as StdMutex,
+};
+
+use log::warn;
+use rmcp::model::{CallToolResult, RawContent, ResourceContents};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::{McpApproval, McpError};
+
+pub const AUDIT_FILE_NAME: &str = "mcp-audit.jsonl";
+
+/// The log is rotated once it grows past this size.
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Rotated files kept next to the current one, `mcp-audit.1.jsonl` being the
+/// most recent.
+const ROTATED_FILES: usize = 3;
+
+/// Text results longer than this are cut in the summary.
+const SUMMARY_LENGTH: usize = 500;
+
+/// One `call_tool` invocation, stored as a line of `mcp-audit.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpAuditEntry {
+  /// Milliseconds since the Unix epoch when the call was made.
+  pub timestamp:   u64,
+  pub server_id:   String,
+  pub tool:        String,
+  pub arguments:   Option<Map<String, Value>>,
+  /// How the call was approved, not set when it was rejected before or by
+  /// the policy.
+  pub approval:    Option<McpApproval>,
+  pub duration_ms: u64,
+  /// Summary of the content the tool returned.
+  pub result:      Option<String>,
+  /// Why the call failed, either an error of the plugin or a result the
+  /// tool flagged with `isError`.
+  pub error:       Option<String>,
+}
+
+impl McpAuditEntry {
+  pub(crate) fn outcome(
+    &mut self,
+    result: &Result<CallToolResult, McpError>,
+  ) {
+    match result {
+      Ok(result) if result.is_error == Some(true) => self.error = Some(summarize(result)),
+      Ok(result) => self.result = Some(summarize(result)),
+      Err(err) => self.error = Some(err.to_string()),
+    }
+  }
+}
+
+/// Short, readable form of a tool result for the audit log. Binary content
+/// is replaced by its type.
+fn summarize(result: &CallToolResult) -> String {
+  let mut summary = result
+    .content
+    .iter()
+    .map(|content| match &content.raw {
+      RawContent::Text(text) => text.text.clone(),
+      RawContent::Image(image) => format!("[image {}]", image.mime_type),
+      RawContent::Audio(audio) => format!("[audio {}]", audio.mime_type),
+      RawContent::Resource(resource) => match &resource.resource {
+        ResourceContents::TextResourceContents { uri, .. }
+        | ResourceContents::BlobResourceContents { uri, .. } => format!("[resource {uri}]"),
+      },
+      RawContent::ResourceLink(link) => format!("[resource link {}]", link.uri),
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+
+  if summary.is_empty() {
+    if let Some(structured) = &result.structured_content {
+      summary = structured.to_string();
+    }
+  }
+
+  if let Some((cut, _)) = summary.char_indices().nth(SUMMARY_LENGTH) {
+    summary.truncate(cut);
+    summary.push('…');
+  }
+  summary
+}
+
+/// Append-only audit log of tool calls, rotated by size.
+#[derive(Debug)]
+pub struct McpAuditLog {
+  /// Unknown when the app data dir could not be resolved, nothing is
+  /// recorded then.
+  path: Option<PathBuf>,
+  lock: StdMutex<()>,
+}
+
+impl McpAuditLog {
+  pub fn new(path: Option<PathBuf>) -> Self {
+    Self {
+      path,
+      lock: StdMutex::new(()),
+    }
+  }
+
+  fn rotated_path(
+    path: &Path,
+    index: usize,
+  ) -> PathBuf {
+    path.with_extension(format!("{index}.jsonl"))
+  }
+
+  /// Append an entry, rotating the log first when it is full.
+  pub fn record(
+    &self,
+    entry: &McpAuditEntry,
+  ) {
+    let Some(path) = &self.path else {
+      return;
+    };
+    let _guard = self
+      .lock
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    if let Err(err) = Self::append(path, entry) {
+      warn!("Failed to write the MCP audit log: {err}");
+    }
+  }
+
+  fn append(
+    path: &Path,
+    entry: &McpAuditEntry,
+  ) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() >= MAX_FILE_SIZE) {
+      for index in (1..ROTATED_FILES).rev() {
+        let from = Self::rotated_path(path, index);
+        if from.exists() {
+          fs::rename(&from, Self::rotated_path(path, index + 1))?;
+        }
+      }
+      fs::rename(path, Self::rotated_path(path, 1))?;
+    }
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)?
+      .write_all(line.as_bytes())?;
+    Ok(())
+  }
+
+  /// Entries matching `filter`, oldest first, across the rotated files.
+  fn query(
+    &self,
+    filter: impl Fn(&McpAuditEntry) -> bool,
+  ) -> anyhow::Result<Vec<McpAuditEntry>> {
+    let Some(path) = &self.path else {
+      return Ok(Vec::new());
+    };
+    let _guard = self
+      .lock
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    let files = (1..=ROTATED_FILES)
+      .rev()
+      .map(|index| Self::rotated_path(path, index))
+      .chain([path.clone()]);
+
+    let mut entries = Vec::new();
+    for file in files {
+      let file = match File::open(&file) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+        Err(err) => return Err(err.into()),
+      };
+
+      for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+          continue;
+        }
+        match serde_json::from_str::<McpAuditEntry>(&line) {
+          Ok(entry) if filter(&entry) => entries.push(entry),
+          Ok(_) => {},
+          // A line cut short by a crash shouldn't hide the rest of the log
+          Err(err) => warn!("Skipping unreadable MCP audit entry: {err}"),
+        }
+      }
+    }
+
+    Ok(entries)
+  }
+}
+
+pub fn audit_path<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<PathBuf> {
+  Ok(app.path().app_data_dir()?.join(AUDIT_FILE_NAME))
+}
+
+pub(crate) fn load<R: Runtime>(app: &AppHandle<R>) -> McpAuditLog {
+  match audit_path(app) {
+    Ok(path) => McpAuditLog::new(Some(path)),
+    Err(err) => {
+      warn!("Failed to resolve the MCP audit log path, tool calls are not recorded: {err}");
+      McpAuditLog::new(None)
+    },
+  }
+}
+
+/// Append an entry on a blocking thread, so the file I/O and rotation don't
+/// stall the async runtime. Returns once the entry is written.
+pub(crate) async fn record<R: Runtime>(
+  app: &AppHandle<R>,
+  entry: McpAuditEntry,
+) {
+  let app = app.clone();
+  if let Err(err) =
+    tauri::async_runtime::spawn_blocking(move || app.state::<McpAuditLog>().record(&entry)).await
+  {
+    warn!("Failed to write the MCP audit log: {err}");
+  }
+}
+
+/// Read the audit log, optionally filtered by the time range `from` to `to`
+/// in milliseconds since the Unix epoch, by server and by tool. With
+/// `limit`, only the most recent entries are returned, still oldest first.
+#[tauri::command]
+pub(crate) async fn query_audit_log<R: Runtime>(
+  app: AppHandle<R>,
+  from: Option<u64>,
+  to: Option<u64>,
+  server_id: Option<String>,
+  tool: Option<String>,
+  limit: Option<usize>,
+) -> Result<Vec<McpAuditEntry>, McpError> {
+  let entries = tauri::async_runtime::spawn_blocking(move || {
+    app.state::<McpAuditLog>().query(|entry| {
+      from.map_or(true, |from| entry.timestamp >= from)
+        && to.map_or(true, |to| entry.timestamp <= to)
+        && server_id
+          .as_ref()
+          .map_or(true, |server_id| &entry.server_id == server_id)
+        && tool
+          .as_ref()
+          .map_or(true, |tool| &entry.tool == tool)
+    })
+  })
+  .await
+  .map_err(|err| McpError::Other(err.to_string()))?
+  .map_err(|err| McpError::Other(format!("Failed to read the MCP audit log: {err}")))?;
+
+  let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+  Ok(entries.into_iter().skip(skip).collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::{Map, Value};
+
+  use super::{McpAuditEntry, McpAuditLog, AUDIT_FILE_NAME, MAX_FILE_SIZE, ROTATED_FILES};
+
+  /// An entry a bit over half of the size limit, a log file holds two.
+  fn entry(timestamp: u64) -> McpAuditEntry {
+    let padding = "x".repeat(MAX_FILE_SIZE as usize / 2);
+    McpAuditEntry {
+      timestamp,
+      server_id: "server".to_string(),
+      tool: "tool".to_string(),
+      arguments: Some(Map::from_iter([(
+        "padding".to_string(),
+        Value::String(padding),
+      )])),
+      approval: None,
+      duration_ms: 0,
+      result: None,
+      error: None,
+    }
+  }
+
+  #[test]
+  fn rotates_by_size_and_drops_the_oldest_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(AUDIT_FILE_NAME);
+    let log = McpAuditLog::new(Some(path.clone()));
+
+    for timestamp in 1..=9 {
+      log.record(&entry(timestamp));
+    }
+
+    for index in 1..=ROTATED_FILES {
+      assert!(McpAuditLog::rotated_path(&path, index).is_file());
+    }
+    assert!(!McpAuditLog::rotated_path(&path, ROTATED_FILES + 1).exists());
+
+    let timestamps: Vec<_> = log
+      .query(|_| true)
+      .unwrap()
+      .iter()
+      .map(|entry| entry.timestamp)
+      .collect();
+    assert_eq!(timestamps, [3, 4, 5, 6, 7, 8, 9]);
+
+    let filtered: Vec<_> = log
+      .query(|entry| entry.timestamp % 2 == 0)
+      .unwrap()
+      .iter()
+      .map(|entry| entry.timestamp)
+      .collect();
+    assert_eq!(filtered, [4, 6, 8]);
+  }
+
+  #[test]
+  fn skips_unreadable_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(AUDIT_FILE_NAME);
+    let log = McpAuditLog::new(Some(path.clone()));
+
+    log.record(&entry(1));
+    std::fs::write(
+      &path,
+      std::fs::read_to_string(&path).unwrap() + "{\"timestamp\":\n",
+    )
+    .unwrap();
+    log.record(&entry(2));
+
+    let timestamps: Vec<_> = log
+      .query(|_| true)
+      .unwrap()
+      .iter()
+      .map(|entry| entry.timestamp)
+      .collect();
+    assert_eq!(timestamps, [1, 2]);
+  }
+}
@@ -0,0 +1,306 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  path::{Path, PathBuf},
+};
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+use tokio::sync::Mutex;
+
+use crate::{roots, tools, McpError, McpRoot, McpSandbox, McpState, McpTransport};
+
+pub const CONFIG_FILE_NAME: &str = "mcp.json";
+
+pub const EVENT_CONFIG_CHANGED: &str = "tauri-plugins:tauri-plugin-mcp:config-changed";
+
+/// Emitted after every config change with the entries that could not be
+/// started, keyed by server id. Empty when all of them were.
+pub const EVENT_CONFIG_ERRORS: &str = "tauri-plugins:tauri-plugin-mcp:config-errors";
+
+/// Contents of `mcp.json` in the app config dir.
+///
+/// Uses the same `mcpServers` layout as other MCP clients, so existing
+/// configurations can be copied over as-is:
+///
+/// ```json
+/// {
+///   "mcpServers": {
+///     "filesystem": {
+///       "command": "npx",
+///       "args": ["-y", "@modelcontextprotocol/server-filesystem", "/home/me"]
+///     },
+///     "browser": { "url": "http://127.0.0.1:8931/mcp", "disabled": true }
+///   },
+///   "roots": [{ "path": "/home/me/projects", "name": "Projects" }]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpConfig {
+  #[serde(default)]
+  pub mcp_servers: BTreeMap<String, McpServerConfig>,
+  /// Directories servers may work in, served through `roots/list`.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub roots:       Vec<McpRoot>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum McpTransportKind {
+  Stdio,
+  #[serde(alias = "http")]
+  StreamableHttp,
+  Sse,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConfig {
+  /// Inferred from `command` / `url` when omitted.
+  #[serde(default, alias = "type", skip_serializing_if = "Option::is_none")]
+  pub transport:    Option<McpTransportKind>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub command:      Option<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub args:         Vec<String>,
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub env:          HashMap<String, String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cwd:          Option<PathBuf>,
+  /// Limits of a stdio server, see [`McpSandbox`].
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sandbox:      Option<McpSandbox>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub url:          Option<String>,
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub headers:      HashMap<String, String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub bearer_token: Option<String>,
+  #[serde(default)]
+  pub disabled:     bool,
+}
+
+impl McpServerConfig {
+  pub fn transport(&self) -> Result<McpTransport, McpError> {
+    let kind = match (self.transport, &self.command, &self.url) {
+      (Some(kind), _, _) => kind,
+      (None, Some(_), _) => McpTransportKind::Stdio,
+      (None, None, Some(_)) => McpTransportKind::StreamableHttp,
+      (None, None, None) => {
+        return Err(McpError::Config(
+          "Either `command` or `url` must be set".to_string(),
+        ))
+      },
+    };
+
+    let url = || {
+      self
+        .url
+        .clone()
+        .ok_or_else(|| McpError::Config("`url` is required for HTTP transports".to_string()))
+    };
+
+    Ok(match kind {
+      McpTransportKind::Stdio => McpTransport::Stdio {
+        command: self.command.clone().ok_or_else(|| {
+          McpError::Config("`command` is required for the stdio transport".to_string())
+        })?,
+        args:    self.args.clone(),
+        env:     self.env.clone(),
+        cwd:     self.cwd.clone(),
+        sandbox: self.sandbox.clone(),
+      },
+      McpTransportKind::StreamableHttp => McpTransport::StreamableHttp {
+        url:          url()?,
+        headers:      self.headers.clone(),
+        bearer_token: self.bearer_token.clone(),
+      },
+      McpTransportKind::Sse => McpTransport::Sse {
+        url:          url()?,
+        headers:      self.headers.clone(),
+        bearer_token: self.bearer_token.clone(),
+      },
+    })
+  }
+}
+
+impl McpConfig {
+  /// Read the config file, returning an empty config when it does not exist yet.
+  pub fn load(path: &Path) -> anyhow::Result<Self> {
+    let config: Self = match std::fs::read_to_string(path) {
+      Ok(content) => serde_json::from_str(&content)?,
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+      Err(err) => return Err(err.into()),
+    };
+
+    config.validate()?;
+    Ok(config)
+  }
+
+  /// Check the server ids, see [`tools::check_server_id`].
+  pub fn validate(&self) -> Result<(), McpError> {
+    self
+      .mcp_servers
+      .keys()
+      .try_for_each(|id| tools::check_server_id(id))
+  }
+
+  pub fn save(
+    &self,
+    path: &Path,
+  ) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+    Ok(())
+  }
+}
+
+pub fn config_path<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<PathBuf> {
+  Ok(
+    app
+      .path()
+      .app_config_dir()?
+      .join(CONFIG_FILE_NAME),
+  )
+}
+
+/// Load the config on startup and connect every server that is not disabled.
+pub(crate) fn autostart<R: Runtime>(app: &AppHandle<R>) {
+  let config = match config_path(app)
+    .map_err(anyhow::Error::from)
+    .and_then(|path| McpConfig::load(&path))
+  {
+    Ok(config) => config,
+    Err(err) => {
+      error!("Failed to load MCP config: {err}");
+      return;
+    },
+  };
+
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    apply(&app, config).await;
+  });
+}
+
+/// Swap in a new config, then reconnect the servers whose entry changed and
+/// disconnect the ones that were removed or disabled. Connected servers are
+/// told when the roots changed.
+///
+/// Returns the entries that could not be started: invalid ones and the ones
+/// whose id is taken by a server connected through `connect_server`, which
+/// keeps running. Failures while connecting are reported by the
+/// `server-status` events of the server instead.
+async fn apply<R: Runtime>(
+  app: &AppHandle<R>,
+  config: McpConfig,
+) -> BTreeMap<String, McpError> {
+  let state = app.state::<Mutex<McpState>>();
+  let (previous, registered, running) = {
+    let mut state = state.lock().await;
+    let previous = std::mem::replace(&mut state.config, config.clone());
+    let registered: Vec<String> = state.servers.keys().cloned().collect();
+    let running: Vec<String> = state
+      .servers
+      .iter()
+      .filter(|(_, server)| server.is_running())
+      .map(|(id, _)| id.clone())
+      .collect();
+    (previous, registered, running)
+  };
+
+  for id in &registered {
+    let Some(server) = previous.mcp_servers.get(id) else {
+      // Connected by hand through `connect_server`, not managed by the config
+      continue;
+    };
+
+    if config.mcp_servers.get(id) != Some(server) {
+      info!("MCP server {id:?} changed in config, disconnecting");
+      if let Err(err) = crate::disconnect(app, id).await {
+        warn!("{err}");
+      }
+    }
+  }
+
+  let mut errors = BTreeMap::new();
+  for (id, server) in &config.mcp_servers {
+    if server.disabled {
+      continue;
+    }
+    if previous.mcp_servers.get(id) == Some(server) && running.contains(id) {
+      continue;
+    }
+    if !previous.mcp_servers.contains_key(id) && running.contains(id) {
+      warn!("MCP server {id:?} is already connected by hand, not starting its config entry");
+      errors.insert(id.clone(), McpError::AlreadyConnected(id.clone()));
+      continue;
+    }
+
+    let transport = match server.transport() {
+      Ok(transport) => transport,
+      Err(err) => {
+        error!("Invalid config for MCP server {id:?}: {err}");
+        errors.insert(id.clone(), err);
+        continue;
+      },
+    };
+
+    let app = app.clone();
+    let id = id.clone();
+    tauri::async_runtime::spawn(async move {
+      if let Err(err) = crate::connect(&app, &id, &transport).await {
+        error!("{err}");
+      }
+    });
+  }
+
+  if previous.roots != config.roots {
+    roots::notify_changed(app).await;
+  }
+
+  if let Err(err) = app.emit(EVENT_CONFIG_CHANGED, &config) {
+    error!("Failed to emit {EVENT_CONFIG_CHANGED}: {err:?}");
+  }
+  if let Err(err) = app.emit(EVENT_CONFIG_ERRORS, &errors) {
+    error!("Failed to emit {EVENT_CONFIG_ERRORS}: {err:?}");
+  }
+
+  errors
+}
+
+#[tauri::command]
+pub(crate) async fn get_config(state: State<'_, Mutex<McpState>>) -> Result<McpConfig, McpError> {
+  Ok(state.lock().await.config.clone())
+}
+
+/// Persist a new config and reconnect the affected servers, returning the
+/// entries that could not be started by server id.
+#[tauri::command]
+pub(crate) async fn set_config<R: Runtime>(
+  app: AppHandle<R>,
+  config: McpConfig,
+) -> Result<BTreeMap<String, McpError>, McpError> {
+  config.validate()?;
+  let path = config_path(&app).map_err(|e| McpError::Config(e.to_string()))?;
+  config
+    .save(&path)
+    .map_err(|e| McpError::Config(e.to_string()))?;
+
+  Ok(apply(&app, config).await)
+}
+
+/// Re-read `mcp.json` after it was edited outside of the app. Entries that
+/// could not be started are reported by the `config-errors` event.
+#[tauri::command]
+pub(crate) async fn reload_config<R: Runtime>(app: AppHandle<R>) -> Result<McpConfig, McpError> {
+  let path = config_path(&app).map_err(|e| McpError::Config(e.to_string()))?;
+  let config = McpConfig::load(&path).map_err(|e| McpError::Config(e.to_string()))?;
+
+  apply(&app, config.clone()).await;
+  Ok(config)
+}
@@ -0,0 +1,120 @@
+use std::{
+  sync::atomic::{AtomicU64, Ordering},
+  time::Duration,
+};
+
+use log::{info, warn};
+use rmcp::{
+  model::{CreateElicitationRequestParam, CreateElicitationResult, ElicitationAction, JsonObject},
+  ErrorData,
+};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::State;
+use tokio::sync::{oneshot, Mutex};
+use tokio_util::sync::CancellationToken;
+
+use crate::{McpError, McpHost, McpState};
+
+pub const EVENT_ELICITATION_REQUEST: &str = "tauri-plugins:tauri-plugin-mcp:elicitation-request";
+
+/// How long the user gets to fill in the form before the request is
+/// cancelled.
+const ELICITATION_TIMEOUT: Duration = Duration::from_secs(600);
+
+static NEXT_ELICITATION_ID: AtomicU64 = AtomicU64::new(1);
+
+pub(crate) type PendingElicitation = oneshot::Sender<CreateElicitationResult>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElicitationRequestPayload {
+  pub elicitation_id:   String,
+  pub server_id:        String,
+  pub message:          String,
+  /// Flat object schema with primitive properties, as restricted by the spec.
+  pub requested_schema: JsonObject,
+}
+
+/// Handle `elicitation/create`: show a form built from the requested schema
+/// in the frontend and wait for the user to submit, decline or dismiss it.
+pub(crate) async fn create_elicitation(
+  host: &dyn McpHost,
+  server_id: &str,
+  params: CreateElicitationRequestParam,
+  ct: CancellationToken,
+) -> Result<CreateElicitationResult, ErrorData> {
+  let elicitation_id = format!(
+    "elicitation-{}",
+    NEXT_ELICITATION_ID.fetch_add(1, Ordering::Relaxed)
+  );
+  let (reply, result) = oneshot::channel();
+  host
+    .mcp_state()
+    .lock()
+    .await
+    .elicitations
+    .insert(elicitation_id.clone(), reply);
+
+  info!("Forwarding elicitation request {elicitation_id} of {server_id:?}");
+  let payload = ElicitationRequestPayload {
+    elicitation_id:   elicitation_id.clone(),
+    server_id:        server_id.to_string(),
+    message:          params.message,
+    requested_schema: params.requested_schema,
+  };
+  match serde_json::to_value(payload) {
+    Ok(payload) => host.emit_event(EVENT_ELICITATION_REQUEST, payload),
+    Err(err) => warn!("Failed to serialize {EVENT_ELICITATION_REQUEST}: {err:?}"),
+  }
+
+  // Anything but an answer from the user counts as dismissing the form
+  let cancelled = CreateElicitationResult {
+    action:  ElicitationAction::Cancel,
+    content: None,
+  };
+  let result = tokio::select! {
+    result = tokio::time::timeout(ELICITATION_TIMEOUT, result) => match result {
+      Ok(Ok(result)) => result,
+      Ok(Err(_)) | Err(_) => cancelled,
+    },
+    () = ct.cancelled() => cancelled,
+  };
+
+  host
+    .mcp_state()
+    .lock()
+    .await
+    .elicitations
+    .remove(&elicitation_id);
+
+  Ok(result)
+}
+
+/// Answer an elicitation request. `content` is only sent along when the user
+/// accepted it.
+#[tauri::command]
+pub(crate) async fn respond_elicitation(
+  state: State<'_, Mutex<McpState>>,
+  elicitation_id: String,
+  action: ElicitationAction,
+  content: Option<Value>,
+) -> Result<(), McpError> {
+  let Some(reply) = state
+    .lock()
+    .await
+    .elicitations
+    .remove(&elicitation_id)
+  else {
+    return Err(McpError::Other(format!(
+      "Elicitation request {elicitation_id} is not pending"
+    )));
+  };
+
+  let content = match action {
+    ElicitationAction::Accept => content,
+    _ => None,
+  };
+  let _ = reply.send(CreateElicitationResult { action, content });
+  Ok(())
+}
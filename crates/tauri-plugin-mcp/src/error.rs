@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use rmcp::{service::ServiceError, ErrorData};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::{McpServerStatus, McpValidationError};
+
+/// Error returned by every command of the plugin.
+///
+/// Serialized as an object with a `kind` tag, a human readable `message` and
+/// the fields of the variant, e.g.
+/// `{ kind: 'rpc', message: 'Tool not found', code: -32602, data: null }`.
+#[derive(Debug, thiserror::Error)]
+pub enum McpError {
+  #[error("MCP server {0} is not connected")]
+  NotConnected(String),
+  #[error("MCP server {0} is already connected")]
+  AlreadyConnected(String),
+  #[error("MCP server {server_id} is not ready ({status:?})")]
+  NotReady {
+    server_id: String,
+    status:    McpServerStatus,
+  },
+  /// The transport is misconfigured, e.g. an invalid URL or header.
+  #[error("Invalid transport: {0}")]
+  InvalidTransport(String),
+  /// The server process could not be started.
+  #[error("Failed to start MCP server: {0}")]
+  Spawn(String),
+  /// The server was reached but the `initialize` exchange failed.
+  #[error("MCP handshake failed: {0}")]
+  Handshake(String),
+  #[error("Connection to the MCP server is closed")]
+  TransportClosed,
+  /// The server answered with a JSON-RPC error.
+  #[error("{message}")]
+  Rpc {
+    code:    i32,
+    message: String,
+    data:    Option<Value>,
+  },
+  #[error("Request timed out after {0:?}")]
+  Timeout(Duration),
+  #[error("Request cancelled{}", .0.as_deref().map(|reason| format!(": {reason}")).unwrap_or_default())]
+  Cancelled(Option<String>),
+  /// The tool call arguments don't match the tool's `inputSchema`, the call
+  /// was not sent.
+  #[error(
+    "Invalid arguments for {tool}: {}",
+    errors
+      .iter()
+      .map(|err| format!("{} ({})", err.message, if err.path.is_empty() { "/" } else { &err.path }))
+      .collect::<Vec<_>>()
+      .join("; ")
+  )]
+  InvalidArguments {
+    tool:   String,
+    errors: Vec<McpValidationError>,
+  },
+  /// The permission policy or the user refused the tool call.
+  #[error("{0}")]
+  Denied(String),
+  #[error("Invalid config: {0}")]
+  Config(String),
+  /// A `${secret:NAME}` placeholder could not be resolved, or the secrets
+  /// file could not be read or written.
+  #[error("{0}")]
+  Secret(String),
+  #[error("{0}")]
+  Other(String),
+}
+
+impl McpError {
+  fn kind(&self) -> &'static str {
+    match self {
+      Self::NotConnected(_) => "not-connected",
+      Self::AlreadyConnected(_) => "already-connected",
+      Self::NotReady { .. } => "not-ready",
+      Self::InvalidTransport(_) => "invalid-transport",
+      Self::Spawn(_) => "spawn",
+      Self::Handshake(_) => "handshake",
+      Self::TransportClosed => "transport-closed",
+      Self::Rpc { .. } => "rpc",
+      Self::Timeout(_) => "timeout",
+      Self::Cancelled(_) => "cancelled",
+      Self::InvalidArguments { .. } => "invalid-arguments",
+      Self::Denied(_) => "denied",
+      Self::Config(_) => "config",
+      Self::Secret(_) => "secret",
+      Self::Other(_) => "other",
+    }
+  }
+}
+
+impl From<ServiceError> for McpError {
+  fn from(err: ServiceError) -> Self {
+    match err {
+      ServiceError::McpError(ErrorData {
+        code,
+        message,
+        data,
+      }) => Self::Rpc {
+        code: code.0,
+        message: message.into_owned(),
+        data,
+      },
+      ServiceError::TransportClosed => Self::TransportClosed,
+      ServiceError::Timeout { timeout } => Self::Timeout(timeout),
+      ServiceError::Cancelled { reason } => Self::Cancelled(reason),
+      err => Self::Other(err.to_string()),
+    }
+  }
+}
+
+impl Serialize for McpError {
+  fn serialize<S: Serializer>(
+    &self,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("kind", self.kind())?;
+    map.serialize_entry("message", &self.to_string())?;
+
+    match self {
+      Self::NotConnected(server_id) | Self::AlreadyConnected(server_id) => {
+        map.serialize_entry("serverId", server_id)?;
+      },
+      Self::NotReady { server_id, status } => {
+        map.serialize_entry("serverId", server_id)?;
+        map.serialize_entry("status", status)?;
+      },
+      Self::Rpc { code, data, .. } => {
+        map.serialize_entry("code", code)?;
+        map.serialize_entry("data", data)?;
+      },
+      Self::InvalidArguments { tool, errors } => {
+        map.serialize_entry("tool", tool)?;
+        map.serialize_entry("errors", errors)?;
+      },
+      Self::Timeout(timeout) => {
+        map.serialize_entry("timeoutMs", &(timeout.as_millis() as u64))?;
+      },
+      _ => {},
+    }
+
+    map.end()
+  }
+}
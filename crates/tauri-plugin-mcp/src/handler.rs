@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use log::{debug, error, info, warn};
+use rmcp::{
+  model::{
+    ClientCapabilities,
+    ClientInfo,
+    CreateElicitationRequestParam,
+    CreateElicitationResult,
+    CreateMessageRequestParam,
+    CreateMessageResult,
+    ElicitationCapability,
+    Implementation,
+    ListRootsResult,
+    LoggingLevel,
+    LoggingMessageNotificationParam,
+    ProgressNotificationParam,
+    ResourceUpdatedNotificationParam,
+    RootsCapabilities,
+  },
+  service::{NotificationContext, RequestContext},
+  ClientHandler,
+  ErrorData,
+  RoleClient,
+};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{Emitter, Manager, Runtime};
+use tokio::sync::Mutex;
+
+use crate::{
+  elicitation,
+  logs::{McpLogEntry, McpLogSource, McpServerLogs, EVENT_SERVER_LOG},
+  roots,
+  sampling,
+  tools::{self, McpProgressChannels},
+  McpState,
+};
+
+pub const EVENT_RESOURCE_UPDATED: &str = "tauri-plugins:tauri-plugin-mcp:resource-updated";
+pub const EVENT_RESOURCE_LIST_CHANGED: &str =
+  "tauri-plugins:tauri-plugin-mcp:resource-list-changed";
+pub const EVENT_PROMPT_LIST_CHANGED: &str = "tauri-plugins:tauri-plugin-mcp:prompt-list-changed";
+pub const EVENT_TOOL_LIST_CHANGED: &str = "tauri-plugins:tauri-plugin-mcp:tool-list-changed";
+
+/// What the client handler needs from the app, without tying the handler to
+/// a Tauri runtime.
+pub trait McpHost: Send + Sync {
+  fn emit_event(
+    &self,
+    event: &str,
+    payload: Value,
+  );
+
+  fn mcp_state(&self) -> &Mutex<McpState>;
+}
+
+impl<R: Runtime> McpHost for tauri::AppHandle<R> {
+  fn emit_event(
+    &self,
+    event: &str,
+    payload: Value,
+  ) {
+    if let Err(err) = self.emit(event, payload) {
+      error!("Failed to emit {event}: {err:?}");
+    }
+  }
+
+  fn mcp_state(&self) -> &Mutex<McpState> {
+    self.state::<Mutex<McpState>>().inner()
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUpdatedPayload {
+  pub server_id: String,
+  pub uri:       String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerEventPayload {
+  pub server_id: String,
+}
+
+/// Client side of an MCP connection, one per connected server.
+#[derive(Clone)]
+pub struct McpClientHandler {
+  server_id: String,
+  host:      Arc<dyn McpHost>,
+  logs:      Arc<McpServerLogs>,
+  progress:  Arc<McpProgressChannels>,
+}
+
+impl McpClientHandler {
+  pub fn new(
+    server_id: String,
+    host: Arc<dyn McpHost>,
+    logs: Arc<McpServerLogs>,
+    progress: Arc<McpProgressChannels>,
+  ) -> Self {
+    Self {
+      server_id,
+      host,
+      logs,
+      progress,
+    }
+  }
+
+  /// Store a log line in the server's buffer and forward it to the webview.
+  pub fn log(
+    &self,
+    source: McpLogSource,
+    level: Option<LoggingLevel>,
+    logger: Option<String>,
+    message: String,
+  ) {
+    debug!("[{}] {message}", self.server_id);
+
+    let entry = McpLogEntry {
+      server_id: self.server_id.clone(),
+      source,
+      level,
+      logger,
+      message,
+      timestamp: crate::unix_millis(),
+    };
+    self.emit(EVENT_SERVER_LOG, &entry);
+    self.logs.push(entry);
+  }
+
+  fn emit(
+    &self,
+    event: &str,
+    payload: impl Serialize,
+  ) {
+    match serde_json::to_value(payload) {
+      Ok(payload) => self.host.emit_event(event, payload),
+      Err(err) => error!("Failed to serialize {event}: {err:?}"),
+    }
+  }
+}
+
+impl ClientHandler for McpClientHandler {
+  async fn create_message(
+    &self,
+    params: CreateMessageRequestParam,
+    context: RequestContext<RoleClient>,
+  ) -> Result<CreateMessageResult, ErrorData> {
+    sampling::create_message(self.host.as_ref(), &self.server_id, params, context.ct).await
+  }
+
+  async fn list_roots(
+    &self,
+    _context: RequestContext<RoleClient>,
+  ) -> Result<ListRootsResult, ErrorData> {
+    Ok(roots::list(self.host.as_ref()).await)
+  }
+
+  async fn create_elicitation(
+    &self,
+    params: CreateElicitationRequestParam,
+    context: RequestContext<RoleClient>,
+  ) -> Result<CreateElicitationResult, ErrorData> {
+    elicitation::create_elicitation(self.host.as_ref(), &self.server_id, params, context.ct).await
+  }
+
+  async fn on_resource_updated(
+    &self,
+    params: ResourceUpdatedNotificationParam,
+    _context: NotificationContext<RoleClient>,
+  ) {
+    info!("Resource {:?} updated on {:?}", params.uri, self.server_id);
+
+    self.emit(
+      EVENT_RESOURCE_UPDATED,
+      ResourceUpdatedPayload {
+        server_id: self.server_id.clone(),
+        uri:       params.uri,
+      },
+    );
+  }
+
+  async fn on_resource_list_changed(
+    &self,
+    _context: NotificationContext<RoleClient>,
+  ) {
+    self.emit(
+      EVENT_RESOURCE_LIST_CHANGED,
+      ServerEventPayload {
+        server_id: self.server_id.clone(),
+      },
+    );
+  }
+
+  async fn on_prompt_list_changed(
+    &self,
+    _context: NotificationContext<RoleClient>,
+  ) {
+    self.emit(
+      EVENT_PROMPT_LIST_CHANGED,
+      ServerEventPayload {
+        server_id: self.server_id.clone(),
+      },
+    );
+  }
+
+  async fn on_tool_list_changed(
+    &self,
+    context: NotificationContext<RoleClient>,
+  ) {
+    info!("Tools of {:?} changed, refreshing", self.server_id);
+
+    // Refresh the cache before telling the webview, so it lists the new tools
+    match context.peer.list_all_tools().await {
+      Ok(tools) => tools::cache(self.host.mcp_state(), &self.server_id, tools).await,
+      Err(err) => warn!("Failed to list tools of {:?}: {err}", self.server_id),
+    }
+
+    self.emit(
+      EVENT_TOOL_LIST_CHANGED,
+      ServerEventPayload {
+        server_id: self.server_id.clone(),
+      },
+    );
+  }
+
+  async fn on_progress(
+    &self,
+    params: ProgressNotificationParam,
+    _context: NotificationContext<RoleClient>,
+  ) {
+    self.progress.send(params);
+  }
+
+  async fn on_logging_message(
+    &self,
+    params: LoggingMessageNotificationParam,
+    _context: NotificationContext<RoleClient>,
+  ) {
+    let message = match params.data {
+      Value::String(message) => message,
+      data => data.to_string(),
+    };
+
+    self.log(
+      McpLogSource::Notification,
+      Some(params.level),
+      params.logger,
+      message,
+    );
+  }
+
+  fn get_info(&self) -> ClientInfo {
+    ClientInfo {
+      capabilities: ClientCapabilities {
+        roots: Some(RootsCapabilities {
+          list_changed: Some(true),
+        }),
+        sampling: Some(Default::default()),
+        // The form only renders the schema, it does not validate the input
+        elicitation: Some(ElicitationCapability {
+          schema_validation: Some(false),
+        }),
+        ..Default::default()
+      },
+      client_info: Implementation {
+        name: "tauri-plugin-mcp".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        ..Default::default()
+      },
+      ..Default::default()
+    }
+  }
+}
@@ -1,144 +1,213 @@
-use std::process::Stdio;
-
-use log::info;
-use rmcp::{
-  model::{CallToolRequestParam, CallToolResult, Tool},
-  service::RunningService,
-  transport::TokioChildProcess,
-  RoleClient,
-  ServiceExt,
-};
-use serde_json::{Map, Value};
+use std::{collections::HashMap, sync::mpsc, time::Duration};
+
+use log::{info, warn};
+use rmcp::{service::Peer, RoleClient};
 use tauri::{
   plugin::{self, TauriPlugin},
   AppHandle,
+  Emitter,
   Manager,
   Runtime,
   State,
 };
-use tokio::{process::Command, sync::Mutex};
+use tokio::sync::Mutex;
+
+mod assets;
+mod audit;
+mod config;
+mod elicitation;
+mod error;
+mod handler;
+mod logs;
+mod policy;
+mod prompts;
+mod resources;
+mod roots;
+mod sampling;
+mod sandbox;
+mod secrets;
+mod supervisor;
+mod tools;
+mod transport;
+mod validation;
 
+pub use assets::{McpAsset, McpAssetKind, McpToolContent, McpToolResult};
+pub use audit::{McpAuditEntry, McpAuditLog};
+pub use config::{McpConfig, McpServerConfig, McpTransportKind};
+pub use error::McpError;
+pub use handler::{McpClientHandler, McpHost};
+pub use logs::{McpLogEntry, McpLogSource, McpServerLogs};
+pub use policy::{McpApproval, McpConsentKind, McpPolicy, McpServerPolicy, McpToolPermission};
+pub use roots::McpRoot;
+pub use sandbox::McpSandbox;
+pub use secrets::McpSecrets;
+pub use supervisor::{McpServer, McpServerExit, McpServerStatus, McpServerSummary};
+pub use tools::{McpNamespacedTool, McpProgressChannels};
+pub use transport::{McpClient, McpTransport};
+pub use validation::McpValidationError;
+
+#[derive(Default)]
 pub struct McpState {
-  pub client: Option<RunningService<RoleClient, ()>>,
+  /// Supervised servers, keyed by the server id chosen by the caller.
+  pub servers:             HashMap<String, McpServer>,
+  /// Servers persisted in `mcp.json`, keyed by the same ids as `servers`.
+  pub config:              McpConfig,
+  /// Running `call_tool`s started with a `request_id`, resolved by
+  /// `cancel_tool_call` with the cancellation reason.
+  pub(crate) tool_calls:   HashMap<String, tools::PendingToolCall>,
+  /// Tool permissions persisted in `mcp-permissions.json`.
+  pub policy:              McpPolicy,
+  /// Consent requests waiting for `respond_consent`, keyed by consent id.
+  pub(crate) consents:     HashMap<String, policy::PendingConsent>,
+  /// Sampling requests waiting for the frontend's completion, keyed by
+  /// sampling id.
+  pub(crate) samplings:    HashMap<String, sampling::PendingSampling>,
+  /// Elicitation forms waiting for `respond_elicitation`, keyed by
+  /// elicitation id.
+  pub(crate) elicitations: HashMap<String, elicitation::PendingElicitation>,
 }
 
-#[allow(clippy::missing_panics_doc)]
-pub fn destroy<R: Runtime>(app_handle: &AppHandle<R>) {
-  info!("Destroying MCP plugin");
+impl McpState {
+  /// Clone the peer of a ready server, so requests can be sent without
+  /// holding the state lock.
+  pub fn peer(
+    &self,
+    id: &str,
+  ) -> Result<Peer<RoleClient>, McpError> {
+    let Some(server) = self.servers.get(id) else {
+      return Err(McpError::NotConnected(id.to_string()));
+    };
 
-  tokio::runtime::Runtime::new()
-    .unwrap()
-    .block_on(async {
-      let state = app_handle.state::<Mutex<McpState>>();
+    server
+      .peer
+      .clone()
+      .ok_or_else(|| McpError::NotReady {
+        server_id: id.to_string(),
+        status:    server.status,
+      })
+  }
+}
 
-      let mut state = state.lock().await;
-      if state.client.is_none() {
-        info!("MCP plugin not connected, no need to disconnect");
-        return;
-      }
+/// Milliseconds since the Unix epoch, for timestamps sent to the frontend.
+pub(crate) fn unix_millis() -> u64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |d| d.as_millis() as u64)
+}
 
-      let client = state.client.take().unwrap();
-      drop(state);
+/// Shut every server down in parallel, each getting a bounded time to exit
+/// gracefully before it is killed. Emits
+/// `tauri-plugins:tauri-plugin-mcp:shutdown` with the ids of the stopped
+/// servers once all of them are down.
+pub async fn shutdown<R: Runtime>(app_handle: &AppHandle<R>) {
+  info!("Shutting down MCP servers");
 
-      client.cancel().await.unwrap();
-      // client.waiting().await.unwrap();
-    });
+  let ids = supervisor::stop_all(app_handle).await;
+  if ids.is_empty() {
+    info!("MCP plugin not connected, no need to disconnect");
+  }
+
+  if let Err(err) = app_handle.emit(supervisor::EVENT_SHUTDOWN, &ids) {
+    warn!("Failed to emit {}: {err:?}", supervisor::EVENT_SHUTDOWN);
+  }
 
-  info!("MCP plugin destroyed");
+  info!("MCP servers shut down");
 }
 
-#[tauri::command]
-async fn connect_server(
-  state: State<'_, Mutex<McpState>>,
-  command: String,
-  args: Vec<String>,
-) -> Result<(), String> {
-  let mut state = state.lock().await;
+/// Blocking variant of [`shutdown`] for synchronous contexts, e.g. when the
+/// plugin is dropped. Returns once every server is down, or after
+/// `SHUTDOWN_TIMEOUT` plus a second to kill the stragglers.
+///
+/// The shutdown runs on a thread of its own, so this blocks inside an async
+/// context too.
+pub fn destroy<R: Runtime>(app_handle: &AppHandle<R>) {
+  info!("Destroying MCP plugin");
 
-  if state.client.is_some() {
-    return Err("Client already connected".to_string());
-  }
+  let (done, finished) = mpsc::channel();
+  let app_handle = app_handle.clone();
+  std::thread::spawn(move || {
+    tauri::async_runtime::block_on(shutdown(&app_handle));
+    let _ = done.send(());
+  });
 
-  let child_process = TokioChildProcess::new(
-    Command::new(command)
-      .args(args)
-      .stderr(Stdio::inherit())
-      .stdout(Stdio::inherit()),
-  )
-  .unwrap();
+  let timeout = supervisor::SHUTDOWN_TIMEOUT + Duration::from_secs(1);
+  if finished.recv_timeout(timeout).is_err() {
+    warn!("MCP servers didn't shut down within {timeout:?}, exiting anyway");
+  }
+}
 
-  let service: RunningService<RoleClient, ()> = ().serve(child_process).await.unwrap();
+pub(crate) async fn connect<R: Runtime>(
+  app: &AppHandle<R>,
+  id: &str,
+  transport: &McpTransport,
+) -> Result<(), McpError> {
+  tools::check_server_id(id)?;
+  supervisor::start(app, id, transport).await?;
 
-  state.client = Some(service);
-  drop(state);
+  info!("Connected to MCP server {id:?}");
 
   Ok(())
 }
 
-#[tauri::command]
-async fn disconnect_server(state: State<'_, Mutex<McpState>>) -> Result<(), String> {
-  let mut state = state.lock().await;
-  if state.client.is_none() {
-    return Err("Client not connected".to_string());
-  }
-
-  let cancel_result = state.client.take().unwrap().cancel().await;
-  info!("Cancel result: {cancel_result:?}");
-  // state.client.take().unwrap().waiting().await.unwrap();
-  drop(state);
+pub(crate) async fn disconnect<R: Runtime>(
+  app: &AppHandle<R>,
+  id: &str,
+) -> Result<(), McpError> {
+  supervisor::stop(app, id).await?;
 
-  info!("Disconnected from MCP server");
+  info!("Disconnected from MCP server {id:?}");
 
   Ok(())
 }
 
 #[tauri::command]
-async fn list_tools(state: State<'_, Mutex<McpState>>) -> Result<Vec<Tool>, String> {
-  let state = state.lock().await;
-  let client = state.client.as_ref();
-  if client.is_none() {
-    return Err("Client not connected".to_string());
-  }
-
-  let list_tools_result = client
-    .unwrap()
-    .list_tools(Option::default())
-    .await
-    .unwrap(); // TODO: handle error
-  let tools = list_tools_result.tools;
-  drop(state);
-
-  Ok(tools)
+async fn connect_server<R: Runtime>(
+  app: AppHandle<R>,
+  id: String,
+  transport: McpTransport,
+) -> Result<(), McpError> {
+  connect(&app, &id, &transport).await
 }
 
 #[tauri::command]
-async fn call_tool(
-  state: State<'_, Mutex<McpState>>,
-  name: String,
-  args: Option<Map<String, Value>>,
-) -> Result<CallToolResult, String> {
-  info!("Calling tool: {name:?}");
-  info!("Arguments: {args:?}");
+async fn disconnect_server<R: Runtime>(
+  app: AppHandle<R>,
+  id: String,
+) -> Result<(), McpError> {
+  disconnect(&app, &id).await
+}
 
+#[tauri::command]
+async fn list_servers(
+  state: State<'_, Mutex<McpState>>
+) -> Result<Vec<McpServerSummary>, McpError> {
   let state = state.lock().await;
-  let client = state.client.as_ref();
-  if client.is_none() {
-    return Err("Client not connected".to_string());
-  }
 
-  let call_tool_result = client
-    .unwrap()
-    .call_tool(CallToolRequestParam {
-      name:      name.into(),
-      arguments: args,
-    })
-    .await
-    .unwrap();
+  let mut servers: Vec<McpServerSummary> = state
+    .servers
+    .iter()
+    .map(|(id, server)| server.summary(id))
+    .collect();
   drop(state);
 
-  info!("Tool result: {call_tool_result:?}");
+  servers.sort_by(|a, b| a.id.cmp(&b.id));
 
-  Ok(call_tool_result)
+  Ok(servers)
+}
+
+/// Status, restart count and last exit of a single server.
+#[tauri::command]
+async fn get_server_status(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+) -> Result<McpServerSummary, McpError> {
+  state
+    .lock()
+    .await
+    .servers
+    .get(&id)
+    .map(|server| server.summary(&id))
+    .ok_or(McpError::NotConnected(id))
 }
 
 #[derive(Default)]
@@ -153,11 +222,47 @@ impl Builder {
       .invoke_handler(tauri::generate_handler![
         connect_server,
         disconnect_server,
-        list_tools,
-        call_tool
+        list_servers,
+        get_server_status,
+        tools::list_tools,
+        tools::list_all_tools,
+        tools::call_tool,
+        tools::cancel_tool_call,
+        audit::query_audit_log,
+        logs::get_server_logs,
+        logs::set_log_level,
+        resources::list_resources,
+        resources::list_resource_templates,
+        resources::read_resource,
+        resources::subscribe_resource,
+        resources::unsubscribe_resource,
+        prompts::list_prompts,
+        prompts::get_prompt,
+        prompts::complete,
+        config::get_config,
+        config::set_config,
+        config::reload_config,
+        roots::get_roots,
+        roots::set_roots,
+        policy::get_policy,
+        policy::set_policy,
+        policy::respond_consent,
+        sampling::respond_sampling,
+        sampling::reject_sampling,
+        elicitation::respond_elicitation,
+        secrets::list_secrets,
+        secrets::set_secret,
+        secrets::delete_secret
       ])
       .setup(|app_handle, _| {
-        app_handle.manage(Mutex::new(McpState { client: None }));
+        app_handle.manage(Mutex::new(McpState {
+          policy: policy::load(app_handle),
+          ..Default::default()
+        }));
+        app_handle.manage(audit::load(app_handle));
+        app_handle.manage(secrets::load(app_handle));
+        assets::setup(app_handle);
+        config::autostart(app_handle);
         Ok(())
       })
       .on_drop(|app_handle: AppHandle<R>| {
@@ -0,0 +1,117 @@
+use std::{collections::VecDeque, sync::Mutex as StdMutex};
+
+use rmcp::model::{LoggingLevel, SetLevelRequestParam};
+use serde::Serialize;
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::{McpError, McpState};
+
+pub const EVENT_SERVER_LOG: &str = "tauri-plugins:tauri-plugin-mcp:server-log";
+
+/// Lines kept per server, older ones are dropped first.
+const LOG_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpLogSource {
+  /// A line the server process wrote to stderr.
+  Stderr,
+  /// A `notifications/message` sent over the MCP connection.
+  Notification,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpLogEntry {
+  pub server_id: String,
+  pub source:    McpLogSource,
+  /// Only known for MCP log notifications.
+  pub level:     Option<LoggingLevel>,
+  pub logger:    Option<String>,
+  pub message:   String,
+  /// Milliseconds since the Unix epoch.
+  pub timestamp: u64,
+}
+
+/// Ring buffer with the most recent log lines of one server.
+///
+/// Shared between the supervisor, the client handler and the stderr reader,
+/// and kept across restarts so the output of a crashed server can still be
+/// read afterwards.
+#[derive(Debug, Default)]
+pub struct McpServerLogs {
+  entries: StdMutex<VecDeque<McpLogEntry>>,
+}
+
+impl McpServerLogs {
+  pub fn push(
+    &self,
+    entry: McpLogEntry,
+  ) {
+    let mut entries = self
+      .entries
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if entries.len() == LOG_CAPACITY {
+      entries.pop_front();
+    }
+    entries.push_back(entry);
+  }
+
+  /// The last `limit` entries, oldest first.
+  pub fn tail(
+    &self,
+    limit: Option<usize>,
+  ) -> Vec<McpLogEntry> {
+    let entries = self
+      .entries
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+    entries.iter().skip(skip).cloned().collect()
+  }
+}
+
+#[tauri::command]
+pub(crate) async fn get_server_logs(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  limit: Option<usize>,
+) -> Result<Vec<McpLogEntry>, McpError> {
+  let state = state.lock().await;
+  let Some(server) = state.servers.get(&id) else {
+    return Err(McpError::NotConnected(id));
+  };
+
+  Ok(server.logs.tail(limit))
+}
+
+/// Ask the server to only send log notifications at `level` or above. The
+/// level is re-applied whenever the server is restarted.
+#[tauri::command]
+pub(crate) async fn set_log_level(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  level: LoggingLevel,
+) -> Result<(), McpError> {
+  let peer = {
+    let mut state = state.lock().await;
+    let Some(server) = state.servers.get_mut(&id) else {
+      return Err(McpError::NotConnected(id));
+    };
+
+    server.log_level = Some(level);
+    server.peer.clone()
+  };
+
+  // Not ready yet, the supervisor sends it once connected
+  let Some(peer) = peer else {
+    return Ok(());
+  };
+
+  peer
+    .set_level(SetLevelRequestParam { level })
+    .await
+    .map_err(McpError::from)
+}
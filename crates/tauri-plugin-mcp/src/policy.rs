@@ -0,0 +1,437 @@
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicU64, Ordering},
+  time::Duration,
+};
+
+use log::{info, warn};
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager, Runtime, State};
+use tokio::sync::{oneshot, Mutex};
+
+use crate::{McpError, McpHost, McpState};
+
+pub const POLICY_FILE_NAME: &str = "mcp-permissions.json";
+
+pub const EVENT_CONSENT_REQUEST: &str = "tauri-plugins:tauri-plugin-mcp:consent-request";
+
+/// Unanswered consent requests are denied after this long.
+const CONSENT_TIMEOUT: Duration = Duration::from_secs(120);
+
+static NEXT_CONSENT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpToolPermission {
+  #[default]
+  Allow,
+  /// Ask the user through a consent request before every call.
+  Ask,
+  Deny,
+}
+
+/// Which tools may be called without asking, stored in
+/// `mcp-permissions.json` in the app config dir.
+///
+/// Rules are looked up from the most to the least specific: the tool's own
+/// rule, the server's default, then the tool's annotations (tools that aren't
+/// `readOnlyHint` or `destructiveHint: false` are asked for, as are tools
+/// without annotations), and finally the global default.
+///
+/// Sampling requests, where a server asks to use the user's LLM, have their
+/// own rule and are asked for unless set otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpPolicy {
+  #[serde(default)]
+  pub default:  McpToolPermission,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sampling: Option<McpToolPermission>,
+  #[serde(default)]
+  pub servers:  BTreeMap<String, McpServerPolicy>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerPolicy {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub default:  Option<McpToolPermission>,
+  /// Keyed by tool name.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub tools:    BTreeMap<String, McpToolPermission>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sampling: Option<McpToolPermission>,
+}
+
+impl McpPolicy {
+  /// Read the policy file, returning the default policy when it does not
+  /// exist yet.
+  pub fn load(path: &Path) -> anyhow::Result<Self> {
+    match std::fs::read_to_string(path) {
+      Ok(content) => Ok(serde_json::from_str(&content)?),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  pub fn save(
+    &self,
+    path: &Path,
+  ) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+    Ok(())
+  }
+
+  /// The rule set by the user for this tool, if any.
+  pub fn rule(
+    &self,
+    server_id: &str,
+    tool: &str,
+  ) -> Option<McpToolPermission> {
+    let server = self.servers.get(server_id)?;
+    server.tools.get(tool).copied().or(server.default)
+  }
+
+  pub fn sampling_rule(
+    &self,
+    server_id: &str,
+  ) -> McpToolPermission {
+    self
+      .servers
+      .get(server_id)
+      .and_then(|server| server.sampling)
+      .or(self.sampling)
+      .unwrap_or(McpToolPermission::Ask)
+  }
+
+  /// Fallback for tools without a rule, based on what the server says about
+  /// the tool.
+  pub fn default_for(
+    &self,
+    tool: Option<&Tool>,
+  ) -> McpToolPermission {
+    // Per the MCP spec, a tool may change anything unless it says otherwise
+    let destructive = tool
+      .and_then(|tool| tool.annotations.as_ref())
+      .map_or(true, |annotations| {
+        annotations.read_only_hint != Some(true) && annotations.destructive_hint != Some(false)
+      });
+
+    if destructive && self.default == McpToolPermission::Allow {
+      McpToolPermission::Ask
+    } else {
+      self.default
+    }
+  }
+}
+
+pub fn policy_path<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<PathBuf> {
+  Ok(
+    app
+      .path()
+      .app_config_dir()?
+      .join(POLICY_FILE_NAME),
+  )
+}
+
+pub(crate) fn load<R: Runtime>(app: &AppHandle<R>) -> McpPolicy {
+  match policy_path(app)
+    .map_err(anyhow::Error::from)
+    .and_then(|path| McpPolicy::load(&path))
+  {
+    Ok(policy) => policy,
+    Err(err) => {
+      warn!("Failed to load MCP permissions, asking for every tool: {err}");
+      McpPolicy {
+        default: McpToolPermission::Ask,
+        ..Default::default()
+      }
+    },
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum McpConsentKind {
+  ToolCall,
+  Sampling,
+}
+
+/// A consent request waiting for `respond_consent`.
+pub(crate) struct PendingConsent {
+  server_id: String,
+  kind:      McpConsentKind,
+  tool:      Option<String>,
+  reply:     oneshot::Sender<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentRequestPayload {
+  pub consent_id: String,
+  pub kind:       McpConsentKind,
+  pub server_id:  String,
+  /// Only set for tool calls.
+  pub tool:       Option<String>,
+  /// Tool call arguments, or the parameters of the sampling request.
+  pub arguments:  Option<Value>,
+  /// The tool's description and annotations, when the server listed it.
+  pub definition: Option<Tool>,
+}
+
+/// Emit a consent request and wait for the user to answer it.
+pub(crate) async fn ask(
+  host: &dyn McpHost,
+  kind: McpConsentKind,
+  server_id: &str,
+  tool: Option<&str>,
+  arguments: Option<Value>,
+  definition: Option<Tool>,
+) -> Result<(), McpError> {
+  let subject = match tool {
+    Some(tool) => format!("Calling {tool} on {server_id}"),
+    None => format!("Sampling for {server_id}"),
+  };
+
+  let consent_id = format!(
+    "consent-{}",
+    NEXT_CONSENT_ID.fetch_add(1, Ordering::Relaxed)
+  );
+  let (reply, approved) = oneshot::channel();
+  host.mcp_state().lock().await.consents.insert(
+    consent_id.clone(),
+    PendingConsent {
+      server_id: server_id.to_string(),
+      kind,
+      tool: tool.map(str::to_string),
+      reply,
+    },
+  );
+
+  info!("Asking for consent: {subject}");
+  let payload = ConsentRequestPayload {
+    consent_id: consent_id.clone(),
+    kind,
+    server_id: server_id.to_string(),
+    tool: tool.map(str::to_string),
+    arguments,
+    definition,
+  };
+  match serde_json::to_value(payload) {
+    Ok(payload) => host.emit_event(EVENT_CONSENT_REQUEST, payload),
+    Err(err) => warn!("Failed to serialize {EVENT_CONSENT_REQUEST}: {err:?}"),
+  }
+
+  let approved = tokio::time::timeout(CONSENT_TIMEOUT, approved).await;
+  host
+    .mcp_state()
+    .lock()
+    .await
+    .consents
+    .remove(&consent_id);
+
+  match approved {
+    Ok(Ok(true)) => Ok(()),
+    Ok(_) => Err(McpError::Denied(format!(
+      "{subject} was denied by the user"
+    ))),
+    Err(_) => Err(McpError::Denied(format!(
+      "No answer to the consent request: {subject}"
+    ))),
+  }
+}
+
+/// How a tool call got past the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum McpApproval {
+  /// Allowed by a rule or the default policy without asking.
+  Auto,
+  /// Allowed by the user through a consent request.
+  User,
+}
+
+/// Check the policy before a tool call goes out, asking the user if needed.
+///
+/// `definition` is the tool as listed by the server, its annotations decide
+/// when the user didn't set a rule.
+pub(crate) async fn authorize<R: Runtime>(
+  app: &AppHandle<R>,
+  server_id: &str,
+  tool: &str,
+  arguments: Option<&Map<String, Value>>,
+  definition: Option<Tool>,
+) -> Result<McpApproval, McpError> {
+  let permission = {
+    let state = app.state::<Mutex<McpState>>();
+    let policy = &state.lock().await.policy;
+    policy
+      .rule(server_id, tool)
+      .unwrap_or_else(|| policy.default_for(definition.as_ref()))
+  };
+
+  match permission {
+    McpToolPermission::Allow => Ok(McpApproval::Auto),
+    McpToolPermission::Deny => Err(McpError::Denied(format!(
+      "Calling {tool} on {server_id} is not allowed"
+    ))),
+    McpToolPermission::Ask => ask(
+      app,
+      McpConsentKind::ToolCall,
+      server_id,
+      Some(tool),
+      arguments.cloned().map(Value::Object),
+      definition,
+    )
+    .await
+    .map(|()| McpApproval::User),
+  }
+}
+
+#[tauri::command]
+pub(crate) async fn get_policy(state: State<'_, Mutex<McpState>>) -> Result<McpPolicy, McpError> {
+  Ok(state.lock().await.policy.clone())
+}
+
+#[tauri::command]
+pub(crate) async fn set_policy<R: Runtime>(
+  app: AppHandle<R>,
+  policy: McpPolicy,
+) -> Result<(), McpError> {
+  let path = policy_path(&app).map_err(|e| McpError::Config(e.to_string()))?;
+  policy
+    .save(&path)
+    .map_err(|e| McpError::Config(e.to_string()))?;
+
+  app.state::<Mutex<McpState>>().lock().await.policy = policy;
+  Ok(())
+}
+
+/// Answer a consent request. With `remember`, the answer is stored as the
+/// tool's (or the server's sampling) rule so the user isn't asked again.
+#[tauri::command]
+pub(crate) async fn respond_consent<R: Runtime>(
+  app: AppHandle<R>,
+  consent_id: String,
+  approved: bool,
+  remember: Option<bool>,
+) -> Result<(), McpError> {
+  let state = app.state::<Mutex<McpState>>();
+  let mut state = state.lock().await;
+  let Some(pending) = state.consents.remove(&consent_id) else {
+    return Err(McpError::Other(format!(
+      "Consent request {consent_id} is not pending"
+    )));
+  };
+
+  let _ = pending.reply.send(approved);
+
+  if remember.unwrap_or_default() {
+    let permission = if approved {
+      McpToolPermission::Allow
+    } else {
+      McpToolPermission::Deny
+    };
+    let server = state
+      .policy
+      .servers
+      .entry(pending.server_id)
+      .or_default();
+    match (pending.kind, pending.tool) {
+      (McpConsentKind::ToolCall, Some(tool)) => {
+        server.tools.insert(tool, permission);
+      },
+      _ => server.sampling = Some(permission),
+    }
+
+    let path = policy_path(&app).map_err(|e| McpError::Config(e.to_string()))?;
+    state
+      .policy
+      .save(&path)
+      .map_err(|e| McpError::Config(e.to_string()))?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use rmcp::model::{JsonObject, Tool, ToolAnnotations};
+
+  use super::{McpPolicy, McpToolPermission};
+
+  fn tool(annotations: Option<ToolAnnotations>) -> Tool {
+    let tool = Tool::new("tool", "A tool", Arc::new(JsonObject::new()));
+    match annotations {
+      Some(annotations) => tool.annotate(annotations),
+      None => tool,
+    }
+  }
+
+  #[test]
+  fn asks_for_tools_without_annotations() {
+    let policy = McpPolicy::default();
+
+    assert_eq!(policy.default_for(None), McpToolPermission::Ask);
+    assert_eq!(
+      policy.default_for(Some(&tool(None))),
+      McpToolPermission::Ask
+    );
+    assert_eq!(
+      policy.default_for(Some(&tool(Some(ToolAnnotations::new())))),
+      McpToolPermission::Ask
+    );
+  }
+
+  #[test]
+  fn asks_for_destructive_tools() {
+    let policy = McpPolicy::default();
+
+    let destructive = tool(Some(ToolAnnotations::new().destructive(true)));
+    assert_eq!(
+      policy.default_for(Some(&destructive)),
+      McpToolPermission::Ask
+    );
+    let writing = tool(Some(ToolAnnotations::new().read_only(false)));
+    assert_eq!(policy.default_for(Some(&writing)), McpToolPermission::Ask);
+  }
+
+  #[test]
+  fn allows_read_only_and_non_destructive_tools() {
+    let policy = McpPolicy::default();
+
+    let read_only = tool(Some(ToolAnnotations::new().read_only(true)));
+    assert_eq!(
+      policy.default_for(Some(&read_only)),
+      McpToolPermission::Allow
+    );
+    let additive = tool(Some(ToolAnnotations::new().destructive(false)));
+    assert_eq!(
+      policy.default_for(Some(&additive)),
+      McpToolPermission::Allow
+    );
+  }
+
+  #[test]
+  fn keeps_a_stricter_global_default() {
+    let read_only = tool(Some(ToolAnnotations::new().read_only(true)));
+    for default in [McpToolPermission::Ask, McpToolPermission::Deny] {
+      let policy = McpPolicy {
+        default,
+        ..Default::default()
+      };
+
+      assert_eq!(policy.default_for(None), default);
+      assert_eq!(policy.default_for(Some(&read_only)), default);
+    }
+  }
+}
@@ -0,0 +1,74 @@
+use rmcp::model::{
+  ArgumentInfo,
+  CompleteRequestParam,
+  CompleteResult,
+  CompletionContext,
+  GetPromptRequestParam,
+  GetPromptResult,
+  ListPromptsResult,
+  PaginatedRequestParam,
+  Reference,
+};
+use serde_json::{Map, Value};
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::{McpError, McpState};
+
+#[tauri::command]
+pub(crate) async fn list_prompts(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  cursor: Option<String>,
+) -> Result<ListPromptsResult, McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  peer
+    .list_prompts(Some(PaginatedRequestParam { cursor }))
+    .await
+    .map_err(McpError::from)
+}
+
+/// Render a prompt template into messages that can be injected into the chat.
+#[tauri::command]
+pub(crate) async fn get_prompt(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  name: String,
+  args: Option<Map<String, Value>>,
+) -> Result<GetPromptResult, McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  peer
+    .get_prompt(GetPromptRequestParam {
+      name,
+      arguments: args,
+    })
+    .await
+    .map_err(McpError::from)
+}
+
+/// Autocomplete the value of a prompt or resource template argument.
+///
+/// `reference` is either `{ type: 'ref/prompt', name }` or
+/// `{ type: 'ref/resource', uri }`, and `context` carries the arguments that
+/// were already filled in.
+#[tauri::command]
+pub(crate) async fn complete(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  reference: Reference,
+  argument: ArgumentInfo,
+  context: Option<CompletionContext>,
+) -> Result<CompleteResult, McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  peer
+    .complete(CompleteRequestParam {
+      r#ref: reference,
+      argument,
+      context,
+    })
+    .await
+    .map_err(McpError::from)
+}
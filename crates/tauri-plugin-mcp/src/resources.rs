@@ -0,0 +1,85 @@
+use rmcp::model::{
+  ListResourceTemplatesResult,
+  ListResourcesResult,
+  PaginatedRequestParam,
+  ReadResourceRequestParam,
+  ReadResourceResult,
+  SubscribeRequestParam,
+  UnsubscribeRequestParam,
+};
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::{McpError, McpState};
+
+#[tauri::command]
+pub(crate) async fn list_resources(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  cursor: Option<String>,
+) -> Result<ListResourcesResult, McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  peer
+    .list_resources(Some(PaginatedRequestParam { cursor }))
+    .await
+    .map_err(McpError::from)
+}
+
+#[tauri::command]
+pub(crate) async fn list_resource_templates(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  cursor: Option<String>,
+) -> Result<ListResourceTemplatesResult, McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  peer
+    .list_resource_templates(Some(PaginatedRequestParam { cursor }))
+    .await
+    .map_err(McpError::from)
+}
+
+#[tauri::command]
+pub(crate) async fn read_resource(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  uri: String,
+) -> Result<ReadResourceResult, McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  peer
+    .read_resource(ReadResourceRequestParam { uri })
+    .await
+    .map_err(McpError::from)
+}
+
+/// Ask the server to send `notifications/resources/updated` for `uri`, which
+/// are forwarded to the webview as `resource-updated` events.
+#[tauri::command]
+pub(crate) async fn subscribe_resource(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  uri: String,
+) -> Result<(), McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  peer
+    .subscribe(SubscribeRequestParam { uri })
+    .await
+    .map_err(McpError::from)
+}
+
+#[tauri::command]
+pub(crate) async fn unsubscribe_resource(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+  uri: String,
+) -> Result<(), McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  peer
+    .unsubscribe(UnsubscribeRequestParam { uri })
+    .await
+    .map_err(McpError::from)
+}
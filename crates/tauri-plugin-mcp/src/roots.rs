@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use log::warn;
+use reqwest::Url;
+use rmcp::model::{ListRootsResult, Root};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+use tokio::sync::Mutex;
+
+use crate::{config, McpError, McpHost, McpState};
+
+/// A directory the user allows servers to work in, e.g. for filesystem
+/// servers. Stored in `mcp.json` next to the servers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpRoot {
+  pub path: PathBuf,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+}
+
+/// Answer `roots/list` with the configured directories as `file://` URIs.
+pub(crate) async fn list(host: &dyn McpHost) -> ListRootsResult {
+  let roots = host.mcp_state().lock().await.config.roots.clone();
+
+  let roots = roots
+    .into_iter()
+    .filter_map(|root| match Url::from_directory_path(&root.path) {
+      Ok(uri) => Some(Root {
+        uri:  uri.to_string(),
+        name: root.name,
+      }),
+      Err(()) => {
+        warn!(
+          "Skipping MCP root {:?}, it is not an absolute path",
+          root.path
+        );
+        None
+      },
+    })
+    .collect();
+
+  ListRootsResult { roots }
+}
+
+/// Send `notifications/roots/list_changed` to every ready server.
+pub(crate) async fn notify_changed<R: Runtime>(app: &AppHandle<R>) {
+  let peers: Vec<_> = app
+    .state::<Mutex<McpState>>()
+    .lock()
+    .await
+    .servers
+    .iter()
+    .filter_map(|(id, server)| Some((id.clone(), server.peer.clone()?)))
+    .collect();
+
+  for (id, peer) in peers {
+    if let Err(err) = peer.notify_roots_list_changed().await {
+      warn!("Failed to notify {id:?} about changed roots: {err}");
+    }
+  }
+}
+
+#[tauri::command]
+pub(crate) async fn get_roots(state: State<'_, Mutex<McpState>>) -> Result<Vec<McpRoot>, McpError> {
+  Ok(state.lock().await.config.roots.clone())
+}
+
+/// Replace the roots in `mcp.json` and tell the connected servers about it.
+#[tauri::command]
+pub(crate) async fn set_roots<R: Runtime>(
+  app: AppHandle<R>,
+  roots: Vec<McpRoot>,
+) -> Result<(), McpError> {
+  let mut config = app
+    .state::<Mutex<McpState>>()
+    .lock()
+    .await
+    .config
+    .clone();
+  config.roots = roots;
+
+  // Entries that fail to start are reported by the `config-errors` event
+  config::set_config(app, config).await?;
+  Ok(())
+}
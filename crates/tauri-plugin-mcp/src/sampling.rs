@@ -0,0 +1,164 @@
+use std::{
+  sync::atomic::{AtomicU64, Ordering},
+  time::Duration,
+};
+
+use log::{info, warn};
+use rmcp::{
+  model::{CreateMessageRequestParam, CreateMessageResult, ErrorCode},
+  ErrorData,
+};
+use serde::Serialize;
+use tauri::State;
+use tokio::sync::{oneshot, Mutex};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+  policy::{self, McpConsentKind, McpToolPermission},
+  McpError,
+  McpHost,
+  McpState,
+};
+
+pub const EVENT_SAMPLING_REQUEST: &str = "tauri-plugins:tauri-plugin-mcp:sampling-request";
+
+/// How long the frontend gets to produce a completion.
+const SAMPLING_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Error code the MCP spec uses when the user rejects a sampling request.
+const USER_REJECTED: ErrorCode = ErrorCode(-1);
+
+static NEXT_SAMPLING_ID: AtomicU64 = AtomicU64::new(1);
+
+pub(crate) type PendingSampling = oneshot::Sender<Result<CreateMessageResult, String>>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingRequestPayload {
+  pub sampling_id: String,
+  pub server_id:   String,
+  /// `messages`, `systemPrompt`, `maxTokens` and the other request fields.
+  #[serde(flatten)]
+  pub params:      CreateMessageRequestParam,
+}
+
+/// Handle `sampling/createMessage`: check the policy, then let the frontend
+/// run the completion with the user's LLM and wait for its result.
+pub(crate) async fn create_message(
+  host: &dyn McpHost,
+  server_id: &str,
+  params: CreateMessageRequestParam,
+  ct: CancellationToken,
+) -> Result<CreateMessageResult, ErrorData> {
+  let permission = host
+    .mcp_state()
+    .lock()
+    .await
+    .policy
+    .sampling_rule(server_id);
+
+  match permission {
+    McpToolPermission::Allow => {},
+    McpToolPermission::Deny => {
+      return Err(ErrorData::new(
+        USER_REJECTED,
+        "Sampling is not allowed for this server",
+        None,
+      ));
+    },
+    McpToolPermission::Ask => {
+      let arguments = serde_json::to_value(&params).ok();
+      policy::ask(
+        host,
+        McpConsentKind::Sampling,
+        server_id,
+        None,
+        arguments,
+        None,
+      )
+      .await
+      .map_err(|err| ErrorData::new(USER_REJECTED, err.to_string(), None))?;
+    },
+  }
+
+  let sampling_id = format!(
+    "sampling-{}",
+    NEXT_SAMPLING_ID.fetch_add(1, Ordering::Relaxed)
+  );
+  let (reply, result) = oneshot::channel();
+  host
+    .mcp_state()
+    .lock()
+    .await
+    .samplings
+    .insert(sampling_id.clone(), reply);
+
+  info!("Forwarding sampling request {sampling_id} of {server_id:?}");
+  let payload = SamplingRequestPayload {
+    sampling_id: sampling_id.clone(),
+    server_id: server_id.to_string(),
+    params,
+  };
+  match serde_json::to_value(payload) {
+    Ok(payload) => host.emit_event(EVENT_SAMPLING_REQUEST, payload),
+    Err(err) => warn!("Failed to serialize {EVENT_SAMPLING_REQUEST}: {err:?}"),
+  }
+
+  let result = tokio::select! {
+    result = tokio::time::timeout(SAMPLING_TIMEOUT, result) => match result {
+      Ok(Ok(result)) => result.map_err(|message| ErrorData::new(USER_REJECTED, message, None)),
+      Ok(Err(_)) => Err(ErrorData::internal_error("Sampling request was dropped", None)),
+      Err(_) => Err(ErrorData::internal_error(
+        format!("No completion after {SAMPLING_TIMEOUT:?}"),
+        None,
+      )),
+    },
+    () = ct.cancelled() => Err(ErrorData::internal_error("Sampling request cancelled", None)),
+  };
+
+  host
+    .mcp_state()
+    .lock()
+    .await
+    .samplings
+    .remove(&sampling_id);
+
+  result
+}
+
+/// Complete a sampling request with the message generated by the frontend.
+#[tauri::command]
+pub(crate) async fn respond_sampling(
+  state: State<'_, Mutex<McpState>>,
+  sampling_id: String,
+  result: CreateMessageResult,
+) -> Result<(), McpError> {
+  let Some(reply) = state.lock().await.samplings.remove(&sampling_id) else {
+    return Err(McpError::Other(format!(
+      "Sampling request {sampling_id} is not pending"
+    )));
+  };
+
+  let _ = reply.send(Ok(result));
+  Ok(())
+}
+
+/// Fail a sampling request, e.g. when no LLM is configured or the completion
+/// failed.
+#[tauri::command]
+pub(crate) async fn reject_sampling(
+  state: State<'_, Mutex<McpState>>,
+  sampling_id: String,
+  message: Option<String>,
+) -> Result<(), McpError> {
+  let Some(reply) = state.lock().await.samplings.remove(&sampling_id) else {
+    return Err(McpError::Other(format!(
+      "Sampling request {sampling_id} is not pending"
+    )));
+  };
+
+  let _ = reply.send(Err(
+    message.unwrap_or_else(|| "Sampling request rejected".to_string()),
+  ));
+  Ok(())
+}
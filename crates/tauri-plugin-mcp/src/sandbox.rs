@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use process_wrap::tokio::TokioCommandWrap;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::process::Command;
+
+use crate::{McpError, McpTransport};
+
+/// Directory under the app data dir the isolated working directories of
+/// servers are created in.
+const SERVERS_DIR_NAME: &str = "mcp-servers";
+
+/// Limits applied to a stdio server when it is spawned, so a runaway or
+/// misbehaving server can't take the app down with it.
+///
+/// Resource limits and process groups only take effect on Linux.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpSandbox {
+  /// Maximum size of the address space in bytes (`RLIMIT_AS`).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_memory_bytes: Option<u64>,
+  /// Maximum CPU time in seconds (`RLIMIT_CPU`), the process is killed once
+  /// it used that much.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_cpu_seconds:  Option<u64>,
+  /// Maximum number of open file descriptors (`RLIMIT_NOFILE`).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_open_files:   Option<u64>,
+  /// Start the server in an empty directory of its own under the app data
+  /// dir when `cwd` is not set, instead of the app's working directory.
+  #[serde(default)]
+  pub isolate_cwd:      bool,
+  /// Environment variables of the app passed on to the server, all others
+  /// are cleared. `env` is added on top. Everything is passed on when not
+  /// set, so e.g. `PATH` usually has to be listed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub env_allowlist:    Option<Vec<String>>,
+  /// Start the server as the leader of a new process group, which is killed
+  /// as a whole on disconnect, including processes the server spawned.
+  #[serde(default)]
+  pub process_group:    bool,
+}
+
+impl McpSandbox {
+  /// Clear the environment down to the allowlist, before `env` is applied.
+  pub(crate) fn apply_env(
+    &self,
+    command: &mut Command,
+  ) {
+    let Some(allowlist) = &self.env_allowlist else {
+      return;
+    };
+
+    command.env_clear();
+    command.envs(std::env::vars_os().filter(|(name, _)| {
+      allowlist
+        .iter()
+        .any(|allowed| name == allowed.as_str())
+    }));
+  }
+
+  /// Set the resource limits in the child between fork and exec.
+  #[cfg(target_os = "linux")]
+  pub(crate) fn apply_limits(
+    &self,
+    command: &mut Command,
+  ) {
+    let limits = [
+      (libc::RLIMIT_AS, self.max_memory_bytes),
+      (libc::RLIMIT_CPU, self.max_cpu_seconds),
+      (libc::RLIMIT_NOFILE, self.max_open_files),
+    ];
+    if limits.iter().all(|(_, limit)| limit.is_none()) {
+      return;
+    }
+
+    // SAFETY: setrlimit is async-signal-safe and nothing is allocated here
+    unsafe {
+      command.pre_exec(move || {
+        for (resource, limit) in limits {
+          let Some(limit) = limit else {
+            continue;
+          };
+          let limit = libc::rlimit {
+            rlim_cur: limit as libc::rlim_t,
+            rlim_max: limit as libc::rlim_t,
+          };
+          if libc::setrlimit(resource, &limit) != 0 {
+            return Err(std::io::Error::last_os_error());
+          }
+        }
+        Ok(())
+      });
+    }
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  pub(crate) fn apply_limits(
+    &self,
+    _command: &mut Command,
+  ) {
+    if self.max_memory_bytes.is_some()
+      || self.max_cpu_seconds.is_some()
+      || self.max_open_files.is_some()
+    {
+      log::warn!("Resource limits for MCP servers are only supported on Linux, ignoring them");
+    }
+  }
+
+  /// Make the server the leader of a new process group. The group is killed
+  /// instead of the single process when the transport is dropped.
+  pub(crate) fn apply_process_group(
+    &self,
+    command: &mut TokioCommandWrap,
+  ) {
+    if !self.process_group {
+      return;
+    }
+
+    #[cfg(target_os = "linux")]
+    command.wrap(process_wrap::tokio::ProcessGroup::leader());
+    #[cfg(not(target_os = "linux"))]
+    {
+      let _ = command;
+      log::warn!("Process groups for MCP servers are only supported on Linux, ignoring the option");
+    }
+  }
+}
+
+/// Point a stdio transport with `isolateCwd` and without `cwd` at the
+/// server's own directory under the app data dir, creating it if needed.
+pub(crate) fn isolate_cwd<R: Runtime>(
+  app: &AppHandle<R>,
+  id: &str,
+  mut transport: McpTransport,
+) -> Result<McpTransport, McpError> {
+  if let McpTransport::Stdio {
+    cwd: cwd @ None,
+    sandbox: Some(McpSandbox {
+      isolate_cwd: true, ..
+    }),
+    ..
+  } = &mut transport
+  {
+    let dir = servers_dir(app)?.join(dir_name(id));
+    std::fs::create_dir_all(&dir).map_err(|err| {
+      McpError::Spawn(format!(
+        "Failed to create the working directory {}: {err}",
+        dir.display()
+      ))
+    })?;
+    *cwd = Some(dir);
+  }
+
+  Ok(transport)
+}
+
+fn servers_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, McpError> {
+  app
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(SERVERS_DIR_NAME))
+    .map_err(|err| McpError::Spawn(format!("Failed to resolve the app data dir: {err}")))
+}
+
+/// Server ids are chosen by the frontend, keep them from escaping the
+/// servers directory.
+fn dir_name(id: &str) -> String {
+  id.chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect()
+}
@@ -0,0 +1,449 @@
+use std::{
+  collections::BTreeMap,
+  fs::{self, OpenOptions},
+  io::Write,
+  path::{Path, PathBuf},
+  sync::Mutex as StdMutex,
+};
+
+use anyhow::{anyhow, Context};
+use chacha20poly1305::{
+  aead::{Aead, AeadCore, KeyInit, OsRng},
+  ChaCha20Poly1305,
+  Key,
+  Nonce,
+};
+use log::warn;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::{McpError, McpTransport};
+
+pub const SECRETS_FILE_NAME: &str = "mcp-secrets.bin";
+
+/// Random key the secrets file is encrypted with, created on first use.
+///
+/// Stored next to the secrets file with owner-only permissions. It is not in
+/// the OS keychain, so whoever can read the app data dir can decrypt the
+/// secrets: the encryption only keeps them out of plain sight.
+pub const KEY_FILE_NAME: &str = "mcp-secrets.key";
+
+const PLACEHOLDER_PREFIX: &str = "${secret:";
+
+const NONCE_LENGTH: usize = 12;
+
+/// Secrets referenced as `${secret:NAME}` in server transports, stored
+/// encrypted with ChaCha20-Poly1305 in the app data dir.
+///
+/// Values are only decrypted when a server is started, the webview can set
+/// them but never read them back. They are not protected at rest from
+/// anyone who can read the app data dir, see [`KEY_FILE_NAME`].
+#[derive(Debug)]
+pub struct McpSecrets {
+  /// Unknown when the app data dir could not be resolved, placeholders can't
+  /// be resolved then.
+  dir:  Option<PathBuf>,
+  lock: StdMutex<()>,
+}
+
+impl McpSecrets {
+  pub fn new(dir: Option<PathBuf>) -> Self {
+    Self {
+      dir,
+      lock: StdMutex::new(()),
+    }
+  }
+
+  fn dir(&self) -> anyhow::Result<&Path> {
+    self
+      .dir
+      .as_deref()
+      .ok_or_else(|| anyhow!("The app data dir is unknown"))
+  }
+
+  fn key(dir: &Path) -> anyhow::Result<Key> {
+    let path = dir.join(KEY_FILE_NAME);
+    match fs::read(&path) {
+      Ok(key) if key.len() == 32 => return Ok(*Key::from_slice(&key)),
+      Ok(_) => return Err(anyhow!("{} is corrupted", path.display())),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => {},
+      Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    }
+
+    // A new key would silently replace every stored secret on the next write
+    let secrets_path = dir.join(SECRETS_FILE_NAME);
+    if secrets_path.exists() {
+      return Err(anyhow!(
+        "{} is missing, the secrets in {} can't be decrypted. Restore the key or delete both files",
+        path.display(),
+        secrets_path.display()
+      ));
+    }
+
+    fs::create_dir_all(dir)?;
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    write_private(&path, &key)?;
+    Ok(key)
+  }
+
+  fn read(dir: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    let path = dir.join(SECRETS_FILE_NAME);
+    let data = match fs::read(&path) {
+      Ok(data) => data,
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+      Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    if data.len() < NONCE_LENGTH {
+      return Err(anyhow!("{} is corrupted", path.display()));
+    }
+
+    let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+    let plaintext = ChaCha20Poly1305::new(&Self::key(dir)?)
+      .decrypt(Nonce::from_slice(nonce), ciphertext)
+      .map_err(|_| {
+        anyhow!(
+          "Failed to decrypt {}, was the key file replaced?",
+          path.display()
+        )
+      })?;
+    Ok(serde_json::from_slice(&plaintext)?)
+  }
+
+  fn write(
+    dir: &Path,
+    secrets: &BTreeMap<String, String>,
+  ) -> anyhow::Result<()> {
+    let key = Self::key(dir)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&key)
+      .encrypt(&nonce, serde_json::to_vec(secrets)?.as_slice())
+      .map_err(|_| anyhow!("Failed to encrypt the MCP secrets"))?;
+
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(&ciphertext);
+
+    // Replace the file in one go, a crash mid-write must not lose every secret
+    let path = dir.join(SECRETS_FILE_NAME);
+    let temp = path.with_extension("bin.tmp");
+    write_private(&temp, &data)?;
+    fs::rename(&temp, &path)?;
+    Ok(())
+  }
+
+  /// Names of the stored secrets, sorted.
+  pub fn names(&self) -> anyhow::Result<Vec<String>> {
+    let _guard = self
+      .lock
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+    Ok(Self::read(self.dir()?)?.into_keys().collect())
+  }
+
+  /// Store a secret, or remove it when `value` is `None`.
+  pub fn set(
+    &self,
+    name: &str,
+    value: Option<String>,
+  ) -> anyhow::Result<()> {
+    let _guard = self
+      .lock
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let dir = self.dir()?;
+
+    let mut secrets = Self::read(dir)?;
+    match value {
+      Some(value) => secrets.insert(name.to_string(), value),
+      None => secrets.remove(name),
+    };
+    Self::write(dir, &secrets)
+  }
+
+  /// A copy of `transport` with the `${secret:NAME}` placeholders in its
+  /// command, arguments, environment, URL and headers replaced.
+  ///
+  /// The secrets file is only read when there is a placeholder.
+  pub fn resolve(
+    &self,
+    transport: &McpTransport,
+  ) -> Result<McpTransport, McpError> {
+    let mut transport = transport.clone();
+
+    let mut fields: Vec<&mut String> = Vec::new();
+    match &mut transport {
+      McpTransport::Stdio {
+        command, args, env, ..
+      } => {
+        fields.push(command);
+        fields.extend(args.iter_mut());
+        fields.extend(env.values_mut());
+      },
+      McpTransport::StreamableHttp {
+        url,
+        headers,
+        bearer_token,
+      }
+      | McpTransport::Sse {
+        url,
+        headers,
+        bearer_token,
+      } => {
+        fields.push(url);
+        fields.extend(headers.values_mut());
+        fields.extend(bearer_token.as_mut());
+      },
+    }
+
+    if !fields
+      .iter()
+      .any(|field| field.contains(PLACEHOLDER_PREFIX))
+    {
+      return Ok(transport);
+    }
+
+    let secrets = {
+      let _guard = self
+        .lock
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+      self
+        .dir()
+        .and_then(Self::read)
+        .map_err(|err| McpError::Secret(format!("Failed to read the MCP secrets: {err}")))?
+    };
+    for field in fields {
+      *field = substitute(field, &secrets)?;
+    }
+
+    Ok(transport)
+  }
+}
+
+/// Write a file only the current user can read.
+fn write_private(
+  path: &Path,
+  data: &[u8],
+) -> anyhow::Result<()> {
+  let mut options = OpenOptions::new();
+  options.create(true).write(true).truncate(true);
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+  }
+
+  options
+    .open(path)
+    .and_then(|mut file| file.write_all(data))
+    .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Replace every `${secret:NAME}` in `value`.
+fn substitute(
+  value: &str,
+  secrets: &BTreeMap<String, String>,
+) -> Result<String, McpError> {
+  let mut resolved = String::with_capacity(value.len());
+  let mut rest = value;
+  while let Some(start) = rest.find(PLACEHOLDER_PREFIX) {
+    resolved.push_str(&rest[..start]);
+    let after = &rest[start + PLACEHOLDER_PREFIX.len()..];
+    let Some(end) = after.find('}') else {
+      return Err(McpError::Secret(
+        "Unterminated secret placeholder, expected `${secret:NAME}`".to_string(),
+      ));
+    };
+
+    let name = &after[..end];
+    let Some(secret) = secrets.get(name) else {
+      return Err(McpError::Secret(format!("Secret {name:?} is not set")));
+    };
+    resolved.push_str(secret);
+    rest = &after[end + 1..];
+  }
+  resolved.push_str(rest);
+
+  Ok(resolved)
+}
+
+fn check_name(name: &str) -> Result<(), McpError> {
+  if name.is_empty()
+    || !name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+  {
+    return Err(McpError::Secret(format!(
+      "Invalid secret name {name:?}, only letters, digits, `_`, `-` and `.` are allowed"
+    )));
+  }
+
+  Ok(())
+}
+
+pub(crate) fn load<R: Runtime>(app: &AppHandle<R>) -> McpSecrets {
+  match app.path().app_data_dir() {
+    Ok(dir) => McpSecrets::new(Some(dir)),
+    Err(err) => {
+      warn!("Failed to resolve the MCP secrets path, secret placeholders can't be used: {err}");
+      McpSecrets::new(None)
+    },
+  }
+}
+
+/// Names of the stored secrets, their values are never sent to the webview.
+#[tauri::command]
+pub(crate) async fn list_secrets(secrets: State<'_, McpSecrets>) -> Result<Vec<String>, McpError> {
+  secrets
+    .names()
+    .map_err(|err| McpError::Secret(err.to_string()))
+}
+
+/// Store a secret to be referenced as `${secret:NAME}`. Servers that are
+/// already running keep the old value until they are restarted.
+#[tauri::command]
+pub(crate) async fn set_secret(
+  secrets: State<'_, McpSecrets>,
+  name: String,
+  value: String,
+) -> Result<(), McpError> {
+  check_name(&name)?;
+  secrets
+    .set(&name, Some(value))
+    .map_err(|err| McpError::Secret(err.to_string()))
+}
+
+#[tauri::command]
+pub(crate) async fn delete_secret(
+  secrets: State<'_, McpSecrets>,
+  name: String,
+) -> Result<(), McpError> {
+  check_name(&name)?;
+  secrets
+    .set(&name, None)
+    .map_err(|err| McpError::Secret(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::{check_name, McpSecrets, KEY_FILE_NAME, SECRETS_FILE_NAME};
+  use crate::{McpError, McpTransport};
+
+  fn transport(
+    header: &str,
+    bearer_token: &str,
+  ) -> McpTransport {
+    McpTransport::Sse {
+      url:          "http://127.0.0.1:8000/sse".to_string(),
+      headers:      HashMap::from([("X-Api-Key".to_string(), header.to_string())]),
+      bearer_token: Some(bearer_token.to_string()),
+    }
+  }
+
+  #[test]
+  fn stores_and_resolves_secrets() {
+    let dir = tempfile::tempdir().unwrap();
+    let secrets = McpSecrets::new(Some(dir.path().to_path_buf()));
+
+    assert!(secrets.names().unwrap().is_empty());
+    secrets
+      .set("TOKEN", Some("very-secret".to_string()))
+      .unwrap();
+    secrets
+      .set("api.key", Some("key-123".to_string()))
+      .unwrap();
+    assert_eq!(secrets.names().unwrap(), ["TOKEN", "api.key"]);
+
+    let stored = std::fs::read(dir.path().join(SECRETS_FILE_NAME)).unwrap();
+    assert!(!String::from_utf8_lossy(&stored).contains("very-secret"));
+
+    let resolved = secrets
+      .resolve(&transport(
+        "prefix-${secret:api.key}-suffix",
+        "${secret:TOKEN}",
+      ))
+      .unwrap();
+    let McpTransport::Sse {
+      headers,
+      bearer_token,
+      ..
+    } = resolved
+    else {
+      panic!("expected an SSE transport");
+    };
+    assert_eq!(headers["X-Api-Key"], "prefix-key-123-suffix");
+    assert_eq!(bearer_token.as_deref(), Some("very-secret"));
+
+    // Read back by a new instance, as after a restart
+    let secrets = McpSecrets::new(Some(dir.path().to_path_buf()));
+    secrets.set("TOKEN", None).unwrap();
+    assert_eq!(secrets.names().unwrap(), ["api.key"]);
+    assert!(matches!(
+      secrets.resolve(&transport("", "${secret:TOKEN}")),
+      Err(McpError::Secret(_))
+    ));
+  }
+
+  #[test]
+  fn resolves_transports_without_placeholders_without_reading_secrets() {
+    let secrets = McpSecrets::new(None);
+
+    let resolved = secrets
+      .resolve(&transport("plain", "token"))
+      .unwrap();
+    let McpTransport::Sse { bearer_token, .. } = resolved else {
+      panic!("expected an SSE transport");
+    };
+    assert_eq!(bearer_token.as_deref(), Some("token"));
+
+    assert!(matches!(
+      secrets.resolve(&transport("", "${secret:TOKEN}")),
+      Err(McpError::Secret(_))
+    ));
+  }
+
+  #[test]
+  fn rejects_unterminated_placeholders() {
+    let dir = tempfile::tempdir().unwrap();
+    let secrets = McpSecrets::new(Some(dir.path().to_path_buf()));
+    secrets
+      .set("TOKEN", Some("very-secret".to_string()))
+      .unwrap();
+
+    assert!(matches!(
+      secrets.resolve(&transport("", "${secret:TOKEN")),
+      Err(McpError::Secret(_))
+    ));
+  }
+
+  #[test]
+  fn keeps_the_secrets_when_the_key_is_missing() {
+    let dir = tempfile::tempdir().unwrap();
+    let secrets = McpSecrets::new(Some(dir.path().to_path_buf()));
+    secrets
+      .set("TOKEN", Some("very-secret".to_string()))
+      .unwrap();
+    let stored = std::fs::read(dir.path().join(SECRETS_FILE_NAME)).unwrap();
+
+    std::fs::remove_file(dir.path().join(KEY_FILE_NAME)).unwrap();
+    assert!(secrets.names().is_err());
+    assert!(secrets
+      .set("OTHER", Some("value".to_string()))
+      .is_err());
+    assert!(!dir.path().join(KEY_FILE_NAME).exists());
+    assert_eq!(
+      std::fs::read(dir.path().join(SECRETS_FILE_NAME)).unwrap(),
+      stored
+    );
+  }
+
+  #[test]
+  fn validates_names() {
+    for name in ["TOKEN", "api.key", "my-secret_2"] {
+      assert!(check_name(name).is_ok(), "{name}");
+    }
+    for name in ["", "with space", "brace}", "slash/name"] {
+      assert!(check_name(name).is_err(), "{name:?}");
+    }
+  }
+}
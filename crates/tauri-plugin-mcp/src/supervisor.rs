@@ -0,0 +1,494 @@
+use std::{
+  pin::pin,
+  sync::Arc,
+  time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+use rmcp::{
+  model::{ClientRequest, LoggingLevel, PingRequest, ServerInfo, SetLevelRequestParam, Tool},
+  service::{Peer, QuitReason},
+  RoleClient,
+};
+use serde::Serialize;
+use tauri::{async_runtime::JoinHandle, AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::{oneshot, Mutex};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+  logs::McpServerLogs,
+  sandbox,
+  tools::McpProgressChannels,
+  McpClient,
+  McpClientHandler,
+  McpError,
+  McpSecrets,
+  McpState,
+  McpTransport,
+};
+
+pub const EVENT_SERVER_STATUS: &str = "tauri-plugins:tauri-plugin-mcp:server-status";
+
+/// Emitted by `shutdown` once every server is down.
+pub const EVENT_SHUTDOWN: &str = "tauri-plugins:tauri-plugin-mcp:shutdown";
+
+/// How often a ready server is pinged.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// A ping that takes longer than this counts as a crash.
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+/// Delay before the first restart, doubled on every consecutive failure.
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(60);
+/// Give up after this many failures in a row.
+const MAX_CONSECUTIVE_RESTARTS: u32 = 8;
+/// A server that stayed up this long is considered healthy again, and the
+/// backoff starts over from `RESTART_BACKOFF_BASE`.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+/// Time a stopped server gets to shut down. rmcp closes the stdin of child
+/// processes and kills them when they don't exit within 3 seconds, the
+/// supervisor is aborted after this.
+pub(crate) const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpServerStatus {
+  Connecting,
+  Ready,
+  /// Exited unexpectedly, either waiting for a restart or given up on.
+  Crashed,
+  Stopped,
+}
+
+/// Why and when a server connection last went down.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerExit {
+  pub reason:    String,
+  /// Milliseconds since the Unix epoch.
+  pub timestamp: u64,
+  /// How long the connection was up, `None` if it never got ready.
+  pub uptime_ms: Option<u64>,
+}
+
+impl McpServerExit {
+  fn new(
+    reason: String,
+    uptime: Option<Duration>,
+  ) -> Self {
+    Self {
+      reason,
+      timestamp: crate::unix_millis(),
+      uptime_ms: uptime.map(|d| d.as_millis() as u64),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerSummary {
+  pub id:          String,
+  pub status:      McpServerStatus,
+  pub server_info: Option<ServerInfo>,
+  /// Number of automatic restarts since `connect_server`.
+  pub restarts:    u32,
+  pub last_exit:   Option<McpServerExit>,
+}
+
+/// A server added through `connect_server` or the config, and the supervisor
+/// task that keeps it running.
+pub struct McpServer {
+  pub transport: McpTransport,
+  pub status:    McpServerStatus,
+  /// Only set while the server is ready.
+  pub peer:      Option<Peer<RoleClient>>,
+  pub restarts:  u32,
+  pub last_exit: Option<McpServerExit>,
+  pub logs:      Arc<McpServerLogs>,
+  pub progress:  Arc<McpProgressChannels>,
+  /// Set through `set_log_level`, sent again after every restart.
+  pub log_level: Option<LoggingLevel>,
+  /// Tools listed by the server, kept until it goes down.
+  pub tools:     Option<Vec<Tool>>,
+  stop:          CancellationToken,
+  task:          Option<JoinHandle<()>>,
+}
+
+impl McpServer {
+  pub fn summary(
+    &self,
+    id: &str,
+  ) -> McpServerSummary {
+    McpServerSummary {
+      id:          id.to_string(),
+      status:      self.status,
+      server_info: self
+        .peer
+        .as_ref()
+        .and_then(|peer| peer.peer_info().cloned()),
+      restarts:    self.restarts,
+      last_exit:   self.last_exit.clone(),
+    }
+  }
+
+  /// Whether the supervisor is still looking after this server.
+  pub fn is_running(&self) -> bool {
+    self
+      .task
+      .as_ref()
+      .is_some_and(|task| !task.inner().is_finished())
+  }
+}
+
+fn emit_status<R: Runtime>(
+  app: &AppHandle<R>,
+  summary: McpServerSummary,
+) {
+  info!("MCP server {:?} is {:?}", summary.id, summary.status);
+
+  if let Err(err) = app.emit(EVENT_SERVER_STATUS, summary) {
+    error!("Failed to emit {EVENT_SERVER_STATUS}: {err:?}");
+  }
+}
+
+/// Register a server and start supervising it, resolving once the first
+/// connection attempt either succeeded or failed.
+///
+/// A server that fails to come up the first time is not retried, so typos in
+/// a command surface as an error right away instead of a restart loop.
+pub(crate) async fn start<R: Runtime>(
+  app: &AppHandle<R>,
+  id: &str,
+  transport: &McpTransport,
+) -> Result<(), McpError> {
+  let (ready_tx, ready_rx) = oneshot::channel();
+
+  {
+    let state = app.state::<Mutex<McpState>>();
+    let mut state = state.lock().await;
+    if state
+      .servers
+      .get(id)
+      .is_some_and(McpServer::is_running)
+    {
+      return Err(McpError::AlreadyConnected(id.to_string()));
+    }
+
+    // Keep the output of a previous, crashed run around
+    let logs = state
+      .servers
+      .get(id)
+      .map(|server| server.logs.clone())
+      .unwrap_or_default();
+
+    let progress = Arc::new(McpProgressChannels::default());
+
+    let stop = CancellationToken::new();
+    let task = tauri::async_runtime::spawn(supervise(
+      app.clone(),
+      id.to_string(),
+      transport.clone(),
+      logs.clone(),
+      progress.clone(),
+      stop.clone(),
+      ready_tx,
+    ));
+
+    // The supervisor waits for this lock before touching the entry, so it
+    // always sees the server registered.
+    state.servers.insert(
+      id.to_string(),
+      McpServer {
+        transport: transport.clone(),
+        status: McpServerStatus::Connecting,
+        peer: None,
+        restarts: 0,
+        last_exit: None,
+        logs,
+        progress,
+        log_level: None,
+        tools: None,
+        stop,
+        task: Some(task),
+      },
+    );
+  }
+
+  ready_rx
+    .await
+    .unwrap_or(Err(McpError::Cancelled(Some(
+      "Server stopped while connecting".to_string(),
+    ))))
+}
+
+/// Stop supervising a server and shut its connection down.
+pub(crate) async fn stop<R: Runtime>(
+  app: &AppHandle<R>,
+  id: &str,
+) -> Result<(), McpError> {
+  let server = {
+    let state = app.state::<Mutex<McpState>>();
+    let mut state = state.lock().await;
+    let Some(server) = state.servers.remove(id) else {
+      return Err(McpError::NotConnected(id.to_string()));
+    };
+    // Cancelled under the lock, so the supervisor never writes to an entry
+    // registered again under the same id afterwards.
+    server.stop.cancel();
+    server
+  };
+
+  finish(app, id, server).await;
+
+  Ok(())
+}
+
+/// Stop every server at once, resolving when all of them are down.
+pub(crate) async fn stop_all<R: Runtime>(app: &AppHandle<R>) -> Vec<String> {
+  let servers: Vec<(String, McpServer)> = {
+    let state = app.state::<Mutex<McpState>>();
+    let mut state = state.lock().await;
+    state
+      .servers
+      .drain()
+      .inspect(|(_, server)| server.stop.cancel())
+      .collect()
+  };
+
+  let tasks: Vec<_> = servers
+    .into_iter()
+    .map(|(id, server)| {
+      let app = app.clone();
+      tauri::async_runtime::spawn(async move {
+        info!("Disconnecting from MCP server {id:?}");
+        finish(&app, &id, server).await;
+        id
+      })
+    })
+    .collect();
+
+  let mut ids = Vec::with_capacity(tasks.len());
+  for task in tasks {
+    match task.await {
+      Ok(id) => ids.push(id),
+      Err(err) => warn!("Failed to stop an MCP server: {err}"),
+    }
+  }
+  ids
+}
+
+/// Wait for the supervisor of a cancelled server to shut the connection
+/// down, aborting it after `SHUTDOWN_TIMEOUT`.
+async fn finish<R: Runtime>(
+  app: &AppHandle<R>,
+  id: &str,
+  server: McpServer,
+) {
+  let mut summary = server.summary(id);
+  if let Some(mut task) = server.task {
+    match tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut task).await {
+      Ok(Ok(())) => {},
+      Ok(Err(err)) => warn!("Supervisor of MCP server {id:?} failed: {err}"),
+      Err(_) => {
+        warn!("MCP server {id:?} did not shut down within {SHUTDOWN_TIMEOUT:?}, aborting it");
+        task.abort();
+      },
+    }
+  }
+
+  summary.status = McpServerStatus::Stopped;
+  summary.server_info = None;
+  emit_status(app, summary);
+}
+
+/// Apply `f` to the server entry and broadcast its new status, unless the
+/// server was stopped in the meantime.
+async fn update<R: Runtime>(
+  app: &AppHandle<R>,
+  id: &str,
+  stop: &CancellationToken,
+  f: impl FnOnce(&mut McpServer),
+) {
+  let summary = {
+    let state = app.state::<Mutex<McpState>>();
+    let mut state = state.lock().await;
+    if stop.is_cancelled() {
+      return;
+    }
+    let Some(server) = state.servers.get_mut(id) else {
+      return;
+    };
+
+    f(server);
+    server.summary(id)
+  };
+
+  emit_status(app, summary);
+}
+
+enum Exit {
+  /// `stop` was cancelled, the connection has been shut down.
+  Stopped,
+  Crashed(String),
+}
+
+async fn supervise<R: Runtime>(
+  app: AppHandle<R>,
+  id: String,
+  transport: McpTransport,
+  logs: Arc<McpServerLogs>,
+  progress: Arc<McpProgressChannels>,
+  stop: CancellationToken,
+  ready: oneshot::Sender<Result<(), McpError>>,
+) {
+  let mut ready = Some(ready);
+  let mut failures = 0;
+
+  loop {
+    update(&app, &id, &stop, |server| {
+      server.status = McpServerStatus::Connecting;
+    })
+    .await;
+
+    let handler = McpClientHandler::new(
+      id.clone(),
+      Arc::new(app.clone()),
+      logs.clone(),
+      progress.clone(),
+    );
+    // Resolved on every attempt, so a restart picks up changed secrets
+    let resolved = app
+      .state::<McpSecrets>()
+      .resolve(&transport)
+      .and_then(|resolved| sandbox::isolate_cwd(&app, &id, resolved));
+    let connected = match resolved {
+      Ok(resolved) => tokio::select! {
+        connected = resolved.connect(handler) => connected,
+        () = stop.cancelled() => break,
+      },
+      Err(err) => Err(err),
+    };
+
+    let (exit, error) = match connected {
+      Ok(service) => {
+        if let Some(ready) = ready.take() {
+          let _ = ready.send(Ok(()));
+        }
+
+        let peer = service.peer().clone();
+        let mut log_level = None;
+        update(&app, &id, &stop, |server| {
+          server.status = McpServerStatus::Ready;
+          server.peer = Some(peer.clone());
+          log_level = server.log_level;
+        })
+        .await;
+
+        if let Some(level) = log_level {
+          if let Err(err) = peer
+            .set_level(SetLevelRequestParam { level })
+            .await
+          {
+            warn!("Failed to set log level of MCP server {id:?}: {err}");
+          }
+        }
+
+        let started = Instant::now();
+        match watch(service, &peer, &stop).await {
+          Exit::Stopped => break,
+          Exit::Crashed(reason) => {
+            if started.elapsed() >= STABLE_AFTER {
+              failures = 0;
+            }
+            (McpServerExit::new(reason, Some(started.elapsed())), None)
+          },
+        }
+      },
+      Err(err) => (McpServerExit::new(err.to_string(), None), Some(err)),
+    };
+
+    warn!("MCP server {id:?} went down: {}", exit.reason);
+
+    let first_attempt = ready.take();
+    failures += 1;
+    let give_up = first_attempt.is_some() || failures > MAX_CONSECUTIVE_RESTARTS;
+
+    update(&app, &id, &stop, |server| {
+      server.status = McpServerStatus::Crashed;
+      server.peer = None;
+      server.tools = None;
+      server.last_exit = Some(exit);
+    })
+    .await;
+
+    if let (Some(ready), Some(error)) = (first_attempt, error) {
+      let _ = ready.send(Err(error));
+    }
+    if give_up {
+      if failures > MAX_CONSECUTIVE_RESTARTS {
+        error!("MCP server {id:?} crashed {failures} times in a row, giving up");
+      }
+      return;
+    }
+
+    let backoff = RESTART_BACKOFF_BASE
+      .saturating_mul(1 << (failures - 1).min(16))
+      .min(RESTART_BACKOFF_MAX);
+    info!("Restarting MCP server {id:?} in {backoff:?}");
+
+    tokio::select! {
+      () = tokio::time::sleep(backoff) => {},
+      () = stop.cancelled() => break,
+    }
+
+    update(&app, &id, &stop, |server| server.restarts += 1).await;
+  }
+
+  info!("Stopped supervising MCP server {id:?}");
+}
+
+/// Wait for the connection to go down, pinging the server in the meantime.
+async fn watch(
+  service: McpClient,
+  peer: &Peer<RoleClient>,
+  stop: &CancellationToken,
+) -> Exit {
+  let cancellation_token = service.cancellation_token();
+  let mut waiting = pin!(service.waiting());
+
+  let mut interval =
+    tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+  interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+  loop {
+    tokio::select! {
+      quit_reason = &mut waiting => {
+        return Exit::Crashed(match quit_reason {
+          Ok(QuitReason::Closed) => "Connection closed".to_string(),
+          Ok(QuitReason::Cancelled) => "Connection cancelled".to_string(),
+          Ok(QuitReason::JoinError(err)) | Err(err) => format!("Client task failed: {err}"),
+        });
+      },
+      () = stop.cancelled() => {
+        cancellation_token.cancel();
+        let _ = waiting.await;
+        return Exit::Stopped;
+      },
+      _ = interval.tick() => {
+        if let Err(reason) = ping(peer).await {
+          cancellation_token.cancel();
+          let _ = waiting.await;
+          return Exit::Crashed(reason);
+        }
+      },
+    }
+  }
+}
+
+async fn ping(peer: &Peer<RoleClient>) -> Result<(), String> {
+  let request = ClientRequest::PingRequest(PingRequest::default());
+  match tokio::time::timeout(PING_TIMEOUT, peer.send_request(request)).await {
+    Ok(Ok(_)) => Ok(()),
+    Ok(Err(err)) => Err(format!("Ping failed: {err}")),
+    Err(_) => Err(format!("Ping timed out after {PING_TIMEOUT:?}")),
+  }
+}
@@ -0,0 +1,478 @@
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+    Mutex as StdMutex,
+    PoisonError,
+  },
+  time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use rmcp::{
+  model::{
+    CallToolRequestParam,
+    CallToolResult,
+    CancelledNotificationParam,
+    ClientRequest,
+    Meta,
+    NumberOrString,
+    ProgressNotificationParam,
+    ProgressToken,
+    Request,
+    ServerResult,
+    Tool,
+  },
+  service::{Peer, PeerRequestOptions},
+  RoleClient,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{ipc::Channel, AppHandle, Manager, Runtime, State};
+use tokio::sync::{oneshot, Mutex};
+
+use crate::{
+  assets::{self, McpToolResult},
+  audit,
+  policy,
+  unix_millis,
+  validation,
+  McpAuditEntry,
+  McpError,
+  McpState,
+};
+
+static NEXT_PROGRESS_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+static NEXT_TOOL_CALL_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A `call_tool` started with a `request_id`, waiting for `cancel_tool_call`.
+pub(crate) struct PendingToolCall {
+  /// Tells this call apart from a later one reusing its `request_id` once
+  /// it was cancelled.
+  call_id: u64,
+  cancel:  oneshot::Sender<Option<String>>,
+}
+
+/// Progress channels of the in-flight tool calls of one server, keyed by the
+/// progress token sent along with the request.
+#[derive(Default)]
+pub struct McpProgressChannels {
+  channels: StdMutex<HashMap<ProgressToken, Channel<ProgressNotificationParam>>>,
+}
+
+impl McpProgressChannels {
+  fn insert(
+    &self,
+    token: ProgressToken,
+    channel: Channel<ProgressNotificationParam>,
+  ) {
+    self
+      .channels
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .insert(token, channel);
+  }
+
+  fn remove(
+    &self,
+    token: &ProgressToken,
+  ) {
+    self
+      .channels
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .remove(token);
+  }
+
+  /// Forward a `notifications/progress` to the caller waiting on the request.
+  pub fn send(
+    &self,
+    params: ProgressNotificationParam,
+  ) {
+    let channels = self
+      .channels
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner);
+    if let Some(channel) = channels.get(&params.progress_token) {
+      if let Err(err) = channel.send(params) {
+        warn!("Failed to send tool call progress: {err}");
+      }
+    }
+  }
+}
+
+/// Registers the progress channel of a tool call and removes it again when
+/// the call ends, however it ends.
+struct ProgressRegistration {
+  channels: Arc<McpProgressChannels>,
+  token:    ProgressToken,
+}
+
+impl ProgressRegistration {
+  fn new(
+    channels: Arc<McpProgressChannels>,
+    channel: Channel<ProgressNotificationParam>,
+  ) -> Self {
+    let token = ProgressToken(NumberOrString::String(
+      format!(
+        "airi-tool-call-{}",
+        NEXT_PROGRESS_TOKEN.fetch_add(1, Ordering::Relaxed)
+      )
+      .into(),
+    ));
+    channels.insert(token.clone(), channel);
+    Self { channels, token }
+  }
+}
+
+impl Drop for ProgressRegistration {
+  fn drop(&mut self) {
+    self.channels.remove(&self.token);
+  }
+}
+
+/// Separates the server id from the tool name in namespaced tool names,
+/// e.g. `filesystem__read_file`.
+pub const NAMESPACE_SEPARATOR: &str = "__";
+
+/// Server ids must not contain the separator, or `a__b` + `c` and `a` +
+/// `b__c` would share the namespaced name `a__b__c`.
+pub fn check_server_id(id: &str) -> Result<(), McpError> {
+  if id.is_empty() || id.contains(NAMESPACE_SEPARATOR) {
+    return Err(McpError::Config(format!(
+      "Invalid MCP server id {id:?}, it must not be empty or contain `{NAMESPACE_SEPARATOR}`"
+    )));
+  }
+
+  Ok(())
+}
+
+/// A tool of one of the connected servers, named `<server id>__<tool name>`
+/// so tools of different servers can't collide.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpNamespacedTool {
+  pub server_id: String,
+  /// The name the server knows the tool by.
+  pub tool_name: String,
+  #[serde(flatten)]
+  pub tool:      Tool,
+}
+
+/// The tools of a server, only listed from the server when they are not
+/// cached yet.
+pub(crate) async fn tools(
+  state: &Mutex<McpState>,
+  peer: &Peer<RoleClient>,
+  id: &str,
+) -> Result<Vec<Tool>, McpError> {
+  if let Some(tools) = state
+    .lock()
+    .await
+    .servers
+    .get(id)
+    .and_then(|server| server.tools.clone())
+  {
+    return Ok(tools);
+  }
+
+  let tools = peer.list_all_tools().await?;
+  cache(state, id, tools.clone()).await;
+
+  Ok(tools)
+}
+
+/// The definition of a tool, `None` when the server doesn't list it.
+pub(crate) async fn definition(
+  state: &Mutex<McpState>,
+  peer: &Peer<RoleClient>,
+  id: &str,
+  name: &str,
+) -> Result<Option<Tool>, McpError> {
+  Ok(
+    tools(state, peer, id)
+      .await?
+      .into_iter()
+      .find(|tool| tool.name == name),
+  )
+}
+
+pub(crate) async fn cache(
+  state: &Mutex<McpState>,
+  id: &str,
+  tools: Vec<Tool>,
+) {
+  if let Some(server) = state.lock().await.servers.get_mut(id) {
+    server.tools = Some(tools);
+  }
+}
+
+/// Tools of a server, from the cache that is refreshed whenever the server
+/// sends `notifications/tools/list_changed`.
+#[tauri::command]
+pub(crate) async fn list_tools(
+  state: State<'_, Mutex<McpState>>,
+  id: String,
+) -> Result<Vec<Tool>, McpError> {
+  let peer = state.lock().await.peer(&id)?;
+
+  tools(&state, &peer, &id).await
+}
+
+/// Tools of every ready server, with namespaced names that can be passed to
+/// `call_tool` without a server id.
+///
+/// Servers failing to list their tools are skipped.
+#[tauri::command]
+pub(crate) async fn list_all_tools(
+  state: State<'_, Mutex<McpState>>
+) -> Result<Vec<McpNamespacedTool>, McpError> {
+  let mut peers: Vec<(String, Peer<RoleClient>)> = state
+    .lock()
+    .await
+    .servers
+    .iter()
+    .filter_map(|(id, server)| Some((id.clone(), server.peer.clone()?)))
+    .collect();
+  peers.sort_by(|a, b| a.0.cmp(&b.0));
+
+  let mut namespaced = Vec::new();
+  for (id, peer) in peers {
+    let tools = match tools(&state, &peer, &id).await {
+      Ok(tools) => tools,
+      Err(err) => {
+        warn!("Failed to list tools of MCP server {id:?}: {err}");
+        continue;
+      },
+    };
+
+    namespaced.extend(tools.into_iter().map(|mut tool| {
+      let tool_name = tool.name.to_string();
+      tool.name = format!("{id}{NAMESPACE_SEPARATOR}{tool_name}").into();
+      McpNamespacedTool {
+        server_id: id.clone(),
+        tool_name,
+        tool,
+      }
+    }));
+  }
+
+  Ok(namespaced)
+}
+
+/// Call a tool without holding the state lock, so slow tools don't block
+/// other commands.
+///
+/// The arguments are checked against the tool's `inputSchema` first, and
+/// with `coerce` simple type mismatches are fixed. Then the call is checked
+/// against the permission policy, which may wait for the user to answer a
+/// consent request.
+///
+/// Without `id`, `name` is taken as a namespaced name from `list_all_tools`.
+///
+/// `request_id` is picked by the caller and can be passed to
+/// `cancel_tool_call` while the call is running. Progress notifications of
+/// the server are streamed through `on_progress`.
+///
+/// Every call is recorded in the audit log, see `query_audit_log`. Images,
+/// audio and embedded resources in the result are written to the assets dir
+/// and returned as references to the files.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn call_tool<R: Runtime>(
+  app: AppHandle<R>,
+  id: Option<String>,
+  name: String,
+  mut args: Option<Map<String, Value>>,
+  coerce: Option<bool>,
+  timeout_ms: Option<u64>,
+  request_id: Option<String>,
+  on_progress: Channel<ProgressNotificationParam>,
+) -> Result<McpToolResult, McpError> {
+  let (id, name) = match id {
+    Some(id) => (id, name),
+    None => match name.split_once(NAMESPACE_SEPARATOR) {
+      Some((id, name)) => (id.to_string(), name.to_string()),
+      None => {
+        return Err(McpError::Other(format!(
+          "{name:?} is not a namespaced tool name, either pass a server id or a name like `server{NAMESPACE_SEPARATOR}tool`"
+        )))
+      },
+    },
+  };
+
+  info!("Calling tool {name:?} on {id:?}");
+  info!("Arguments: {args:?}");
+
+  let started = Instant::now();
+  let mut entry = McpAuditEntry {
+    timestamp:   unix_millis(),
+    server_id:   id.clone(),
+    tool:        name.clone(),
+    arguments:   args.clone(),
+    approval:    None,
+    duration_ms: 0,
+    result:      None,
+    error:       None,
+  };
+
+  let state = app.state::<Mutex<McpState>>();
+  let result = async {
+    let peer = state.lock().await.peer(&id)?;
+    let definition = definition(&state, &peer, &id, &name).await?;
+    if let Some(definition) = &definition {
+      validation::validate(definition, &mut args, coerce.unwrap_or_default())?;
+    }
+    // Record what was actually sent, after coercion
+    entry.arguments.clone_from(&args);
+    entry.approval = Some(policy::authorize(&app, &id, &name, args.as_ref(), definition).await?);
+
+    send(
+      &state,
+      &peer,
+      &id,
+      &name,
+      args,
+      timeout_ms,
+      request_id,
+      on_progress,
+    )
+    .await
+  }
+  .await;
+
+  entry.duration_ms = started.elapsed().as_millis() as u64;
+  entry.outcome(&result);
+  audit::record(&app, entry).await;
+
+  info!("Tool result: {result:?}");
+
+  assets::convert(&app, result?).await
+}
+
+/// Send an authorized `tools/call` and wait for the result, or for
+/// `cancel_tool_call`.
+#[allow(clippy::too_many_arguments)]
+async fn send(
+  state: &Mutex<McpState>,
+  peer: &Peer<RoleClient>,
+  id: &str,
+  name: &str,
+  args: Option<Map<String, Value>>,
+  timeout_ms: Option<u64>,
+  request_id: Option<String>,
+  on_progress: Channel<ProgressNotificationParam>,
+) -> Result<CallToolResult, McpError> {
+  let call_id = NEXT_TOOL_CALL_ID.fetch_add(1, Ordering::Relaxed);
+  let (progress, cancelled) = {
+    let mut state = state.lock().await;
+    let Some(server) = state.servers.get(id) else {
+      return Err(McpError::NotConnected(id.to_string()));
+    };
+    let progress = server.progress.clone();
+
+    let cancelled = match &request_id {
+      Some(request_id) if state.tool_calls.contains_key(request_id) => {
+        return Err(McpError::Other(format!(
+          "Tool call {request_id} is already running"
+        )));
+      },
+      Some(request_id) => {
+        let (cancel, cancelled) = oneshot::channel();
+        state
+          .tool_calls
+          .insert(request_id.clone(), PendingToolCall { call_id, cancel });
+        Some(cancelled)
+      },
+      None => None,
+    };
+
+    (progress, cancelled)
+  };
+
+  let request = ClientRequest::CallToolRequest(Request::new(CallToolRequestParam {
+    name:      name.to_string().into(),
+    arguments: args,
+  }));
+  // Registered before sending, the server may report progress right away
+  let progress = ProgressRegistration::new(progress, on_progress);
+  let mut meta = Meta::new();
+  meta.set_progress_token(progress.token.clone());
+  let options = PeerRequestOptions {
+    timeout: timeout_ms.map(Duration::from_millis),
+    meta:    Some(meta),
+  };
+
+  let result = async {
+    let handle = peer
+      .send_request_with_option(request, options)
+      .await?;
+    let rpc_id = handle.id.clone();
+
+    let result = match cancelled {
+      Some(cancelled) => {
+        tokio::select! {
+          response = handle.await_response() => response.map_err(McpError::from),
+          Ok(reason) = cancelled => {
+            // The server drops the request, no response is coming anymore
+            if let Err(err) = peer
+              .notify_cancelled(CancelledNotificationParam {
+                request_id: rpc_id,
+                reason:     reason.clone(),
+              })
+              .await
+            {
+              warn!("Failed to notify MCP server {id:?} of the cancellation: {err}");
+            }
+            Err(McpError::Cancelled(reason))
+          },
+        }
+      },
+      None => handle
+        .await_response()
+        .await
+        .map_err(McpError::from),
+    };
+
+    match result? {
+      ServerResult::CallToolResult(result) => Ok(result),
+      _ => Err(McpError::Other(
+        "Unexpected response to tools/call".to_string(),
+      )),
+    }
+  }
+  .await;
+
+  if let Some(request_id) = &request_id {
+    // Once cancelled, the id may already belong to a new call
+    let mut state = state.lock().await;
+    if state
+      .tool_calls
+      .get(request_id)
+      .is_some_and(|call| call.call_id == call_id)
+    {
+      state.tool_calls.remove(request_id);
+    }
+  }
+
+  result
+}
+
+/// Cancel a running `call_tool` by the `request_id` it was started with.
+#[tauri::command]
+pub(crate) async fn cancel_tool_call(
+  state: State<'_, Mutex<McpState>>,
+  request_id: String,
+  reason: Option<String>,
+) -> Result<(), McpError> {
+  let Some(call) = state.lock().await.tool_calls.remove(&request_id) else {
+    return Err(McpError::Other(format!(
+      "Tool call {request_id} is not running"
+    )));
+  };
+
+  let _ = call.cancel.send(reason);
+  Ok(())
+}
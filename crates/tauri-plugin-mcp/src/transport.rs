@@ -0,0 +1,197 @@
+use std::{collections::HashMap, path::PathBuf, process::Stdio};
+
+use process_wrap::tokio::TokioCommandWrap;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use rmcp::{
+  service::RunningService,
+  transport::{
+    sse_client::SseClientConfig,
+    streamable_http_client::StreamableHttpClientTransportConfig,
+    IntoTransport,
+    SseClientTransport,
+    StreamableHttpClientTransport,
+    TokioChildProcess,
+  },
+  RoleClient,
+  ServiceExt,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+  io::{AsyncBufReadExt, BufReader},
+  process::Command,
+};
+
+use crate::{handler::McpClientHandler, logs::McpLogSource, McpError, McpSandbox};
+
+/// How the plugin reaches an MCP server.
+///
+/// Serialized with a `type` tag so the frontend can pass e.g.
+/// `{ type: 'streamable-http', url: 'http://127.0.0.1:8000/mcp' }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum McpTransport {
+  /// Spawn a child process and talk JSON-RPC over its stdin / stdout.
+  Stdio {
+    command: String,
+    #[serde(default)]
+    args:    Vec<String>,
+    #[serde(default)]
+    env:     HashMap<String, String>,
+    cwd:     Option<PathBuf>,
+    #[serde(default)]
+    sandbox: Option<McpSandbox>,
+  },
+  /// MCP Streamable HTTP transport (protocol revision 2025-03-26 and later).
+  #[serde(rename_all = "camelCase")]
+  StreamableHttp {
+    url:          String,
+    #[serde(default)]
+    headers:      HashMap<String, String>,
+    bearer_token: Option<String>,
+  },
+  /// Legacy HTTP + SSE transport (protocol revision 2024-11-05).
+  #[serde(rename_all = "camelCase")]
+  Sse {
+    url:          String,
+    #[serde(default)]
+    headers:      HashMap<String, String>,
+    bearer_token: Option<String>,
+  },
+}
+
+pub type McpClient = RunningService<RoleClient, McpClientHandler>;
+
+impl McpTransport {
+  /// Open the transport and run the MCP initialization handshake.
+  pub async fn connect(
+    &self,
+    handler: McpClientHandler,
+  ) -> Result<McpClient, McpError> {
+    match self {
+      McpTransport::Stdio {
+        command,
+        args,
+        env,
+        cwd,
+        sandbox,
+      } => {
+        let sandbox = sandbox.clone().unwrap_or_default();
+
+        let mut command = Command::new(command);
+        command.args(args);
+        sandbox.apply_env(&mut command);
+        command.envs(env);
+        if let Some(cwd) = cwd {
+          command.current_dir(cwd);
+        }
+        sandbox.apply_limits(&mut command);
+
+        let mut command = TokioCommandWrap::from(command);
+        sandbox.apply_process_group(&mut command);
+
+        let (child_process, stderr) = TokioChildProcess::builder(command)
+          .stderr(Stdio::piped())
+          .spawn()
+          .map_err(|e| McpError::Spawn(e.to_string()))?;
+
+        // Ends by itself once the process exits and the pipe is closed
+        if let Some(stderr) = stderr {
+          let handler = handler.clone();
+          tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+              handler.log(McpLogSource::Stderr, None, None, line);
+            }
+          });
+        }
+
+        handshake(handler, child_process).await
+      },
+      McpTransport::StreamableHttp {
+        url,
+        headers,
+        bearer_token,
+      } => {
+        let client = http_client(headers, bearer_token.as_deref())?;
+        let transport = StreamableHttpClientTransport::with_client(
+          client,
+          StreamableHttpClientTransportConfig::with_uri(check_url(url)?),
+        );
+
+        handshake(handler, transport).await
+      },
+      McpTransport::Sse {
+        url,
+        headers,
+        bearer_token,
+      } => {
+        let client = http_client(headers, bearer_token.as_deref())?;
+        let transport = SseClientTransport::start_with_client(
+          client,
+          SseClientConfig {
+            sse_endpoint: check_url(url)?.into(),
+            ..Default::default()
+          },
+        )
+        .await
+        .map_err(|e| McpError::Handshake(e.to_string()))?;
+
+        handshake(handler, transport).await
+      },
+    }
+  }
+}
+
+async fn handshake<T, E, A>(
+  handler: McpClientHandler,
+  transport: T,
+) -> Result<McpClient, McpError>
+where
+  T: IntoTransport<RoleClient, E, A>,
+  E: std::error::Error + Send + Sync + 'static,
+{
+  handler
+    .serve(transport)
+    .await
+    .map_err(|e| McpError::Handshake(e.to_string()))
+}
+
+fn check_url(url: &str) -> Result<&str, McpError> {
+  let parsed = reqwest::Url::parse(url).map_err(|e| McpError::InvalidTransport(e.to_string()))?;
+  if !matches!(parsed.scheme(), "http" | "https") {
+    return Err(McpError::InvalidTransport(format!(
+      "Unsupported URL scheme {:?}, expected http or https",
+      parsed.scheme()
+    )));
+  }
+
+  Ok(url)
+}
+
+/// Build a reqwest client that sends the custom headers (and the bearer token,
+/// if any) with every request, including the long-lived SSE streams.
+fn http_client(
+  headers: &HashMap<String, String>,
+  bearer_token: Option<&str>,
+) -> Result<reqwest::Client, McpError> {
+  let invalid = |e: &dyn std::fmt::Display| McpError::InvalidTransport(e.to_string());
+
+  let mut default_headers = HeaderMap::new();
+  for (name, value) in headers {
+    default_headers.insert(
+      HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(&e))?,
+      HeaderValue::from_str(value).map_err(|e| invalid(&e))?,
+    );
+  }
+
+  if let Some(token) = bearer_token {
+    let mut value = HeaderValue::from_str(&format!("Bearer {token}")).map_err(|e| invalid(&e))?;
+    value.set_sensitive(true);
+    default_headers.insert(AUTHORIZATION, value);
+  }
+
+  reqwest::Client::builder()
+    .default_headers(default_headers)
+    .build()
+    .map_err(|e| invalid(&e))
+}
@@ -0,0 +1,271 @@
+use log::{info, warn};
+use rmcp::model::Tool;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::McpError;
+
+/// One way the arguments of a tool call don't match the tool's
+/// `inputSchema`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpValidationError {
+  /// JSON pointer to the offending argument, empty for the arguments object
+  /// itself.
+  pub path:        String,
+  /// JSON pointer to the schema keyword that failed.
+  pub schema_path: String,
+  pub message:     String,
+}
+
+/// Check the arguments of a tool call against the tool's `inputSchema`.
+///
+/// With `coerce`, arguments of the wrong primitive type are converted when
+/// that is unambiguous, e.g. `"42"` for an integer or a JSON encoded object
+/// passed as a string, before the arguments are checked again.
+pub(crate) fn validate(
+  tool: &Tool,
+  arguments: &mut Option<Map<String, Value>>,
+  coerce: bool,
+) -> Result<(), McpError> {
+  let schema = Value::Object(tool.input_schema.as_ref().clone());
+  let validator = match jsonschema::validator_for(&schema) {
+    Ok(validator) => validator,
+    Err(err) => {
+      // The server's schema is broken, let the server judge the arguments
+      warn!("Invalid inputSchema of tool {:?}: {err}", tool.name);
+      return Ok(());
+    },
+  };
+
+  let errors = |arguments: &Option<Map<String, Value>>| -> Vec<McpValidationError> {
+    let instance = Value::Object(arguments.clone().unwrap_or_default());
+    validator
+      .iter_errors(&instance)
+      .map(|err| McpValidationError {
+        path:        err.instance_path.to_string(),
+        schema_path: err.schema_path.to_string(),
+        message:     err.to_string(),
+      })
+      .collect()
+  };
+
+  let mut found = errors(arguments);
+  if found.is_empty() {
+    return Ok(());
+  }
+
+  if coerce {
+    if let (Some(arguments), Some(Value::Object(properties))) =
+      (arguments.as_mut(), schema.get("properties"))
+    {
+      for (name, value) in arguments.iter_mut() {
+        let Some(types) = properties.get(name).map(schema_types) else {
+          continue;
+        };
+        if let Some(coerced) = coerce_value(value, &types) {
+          info!("Coerced argument {name:?} of {:?} to {coerced}", tool.name);
+          *value = coerced;
+        }
+      }
+    }
+
+    found = errors(arguments);
+    if found.is_empty() {
+      return Ok(());
+    }
+  }
+
+  Err(McpError::InvalidArguments {
+    tool:   tool.name.to_string(),
+    errors: found,
+  })
+}
+
+/// The JSON types a property schema allows.
+fn schema_types(schema: &Value) -> Vec<&str> {
+  match schema.get("type") {
+    Some(Value::String(kind)) => vec![kind.as_str()],
+    Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+    _ => Vec::new(),
+  }
+}
+
+fn type_of(value: &Value) -> &'static str {
+  match value {
+    Value::Null => "null",
+    Value::Bool(_) => "boolean",
+    Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+    Value::Number(_) => "number",
+    Value::String(_) => "string",
+    Value::Array(_) => "array",
+    Value::Object(_) => "object",
+  }
+}
+
+/// Convert a value to the first of `types` it can be converted to without
+/// guessing, or `None` when it already has an allowed type.
+fn coerce_value(
+  value: &Value,
+  types: &[&str],
+) -> Option<Value> {
+  let current = type_of(value);
+  if types.contains(&current) || (current == "integer" && types.contains(&"number")) {
+    return None;
+  }
+
+  types
+    .iter()
+    .find_map(|target| match (value, *target) {
+      (Value::String(text), "integer") => text.trim().parse::<i64>().ok().map(Value::from),
+      (Value::String(text), "number") => text
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(Number::from_f64)
+        .map(Value::Number),
+      (Value::String(text), "boolean") => match text.trim() {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ => None,
+      },
+      (Value::String(text), "object" | "array") => serde_json::from_str::<Value>(text)
+        .ok()
+        .filter(|parsed| type_of(parsed) == *target),
+      (Value::Number(number), "integer") => number
+        .as_f64()
+        .filter(|number| number.fract() == 0.0)
+        .map(|number| Value::from(number as i64)),
+      (Value::Number(_) | Value::Bool(_), "string") => Some(Value::String(value.to_string())),
+      _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use rmcp::model::{object, Tool};
+  use serde_json::{json, Map, Value};
+
+  use super::validate;
+  use crate::McpError;
+
+  fn tool() -> Tool {
+    Tool::new(
+      "tool",
+      "A tool",
+      Arc::new(object(json!({
+        "type": "object",
+        "properties": {
+          "count": { "type": "integer" },
+          "ratio": { "type": "number" },
+          "enabled": { "type": "boolean" },
+          "name": { "type": "string" },
+          "options": { "type": "object" },
+          "tags": { "type": "array" },
+        },
+        "required": ["count"],
+      }))),
+    )
+  }
+
+  fn arguments(value: Value) -> Option<Map<String, Value>> {
+    match value {
+      Value::Object(arguments) => Some(arguments),
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn accepts_valid_arguments_unchanged() {
+    let mut args = arguments(json!({ "count": 1, "name": "airi" }));
+
+    validate(&tool(), &mut args, false).unwrap();
+    assert_eq!(args, arguments(json!({ "count": 1, "name": "airi" })));
+  }
+
+  #[test]
+  fn coerces_primitives() {
+    let mut args = arguments(json!({
+      "count": " 42 ",
+      "ratio": "0.5",
+      "enabled": "true",
+      "name": 7,
+    }));
+
+    validate(&tool(), &mut args, true).unwrap();
+    assert_eq!(
+      args,
+      arguments(json!({
+        "count": 42,
+        "ratio": 0.5,
+        "enabled": true,
+        "name": "7",
+      }))
+    );
+  }
+
+  #[test]
+  fn coerces_json_encoded_objects_and_arrays() {
+    let mut args = arguments(json!({
+      "count": 2.0,
+      "options": "{\"depth\": 1}",
+      "tags": "[\"a\", \"b\"]",
+    }));
+
+    validate(&tool(), &mut args, true).unwrap();
+    assert_eq!(
+      args,
+      arguments(json!({
+        "count": 2,
+        "options": { "depth": 1 },
+        "tags": ["a", "b"],
+      }))
+    );
+  }
+
+  #[test]
+  fn rejects_mistyped_arguments_without_coercion() {
+    let mut args = arguments(json!({ "count": "42" }));
+
+    let Err(McpError::InvalidArguments { tool, errors }) = validate(&tool(), &mut args, false)
+    else {
+      panic!("expected invalid arguments");
+    };
+    assert_eq!(tool, "tool");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path, "/count");
+    assert_eq!(args, arguments(json!({ "count": "42" })));
+  }
+
+  #[test]
+  fn rejects_arguments_that_cant_be_coerced() {
+    let mut args = arguments(json!({
+      "count": "forty-two",
+      "enabled": "yes",
+      "options": "[1]",
+    }));
+
+    let Err(McpError::InvalidArguments { errors, .. }) = validate(&tool(), &mut args, true) else {
+      panic!("expected invalid arguments");
+    };
+    let mut paths: Vec<_> = errors
+      .iter()
+      .map(|error| error.path.as_str())
+      .collect();
+    paths.sort_unstable();
+    assert_eq!(paths, ["/count", "/enabled", "/options"]);
+  }
+
+  #[test]
+  fn reports_missing_required_arguments() {
+    let mut args = None;
+
+    let Err(McpError::InvalidArguments { errors, .. }) = validate(&tool(), &mut args, true) else {
+      panic!("expected invalid arguments");
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path, "");
+  }
+}
@@ -0,0 +1,222 @@
+//! Connects to in-process MCP servers over both HTTP transports, checking
+//! that the custom headers and the bearer token reach the server.
+
+use std::{collections::HashMap, future::Future, net::SocketAddr, sync::Arc};
+
+use axum::{
+  extract::Request,
+  http::{header::AUTHORIZATION, StatusCode},
+  middleware::{self, Next},
+  response::Response,
+  Router,
+};
+use rmcp::{
+  model::{
+    JsonObject,
+    ListToolsResult,
+    PaginatedRequestParam,
+    ServerCapabilities,
+    ServerInfo,
+    Tool,
+  },
+  service::RequestContext,
+  transport::{
+    sse_server::{SseServer, SseServerConfig},
+    streamable_http_server::{session::local::LocalSessionManager, StreamableHttpService},
+  },
+  ErrorData,
+  RoleServer,
+  ServerHandler,
+};
+use serde_json::Value;
+use tauri_plugin_mcp::{McpClientHandler, McpHost, McpState, McpTransport};
+use tokio::{net::TcpListener, sync::Mutex};
+use tokio_util::sync::CancellationToken;
+
+const HEADER: &str = "x-airi-test";
+const HEADER_VALUE: &str = "header-value";
+const TOKEN: &str = "secret-token";
+
+#[derive(Default)]
+struct TestHost {
+  state: Mutex<McpState>,
+}
+
+impl McpHost for TestHost {
+  fn emit_event(
+    &self,
+    _event: &str,
+    _payload: Value,
+  ) {
+  }
+
+  fn mcp_state(&self) -> &Mutex<McpState> {
+    &self.state
+  }
+}
+
+fn handler() -> McpClientHandler {
+  McpClientHandler::new(
+    "test".to_string(),
+    Arc::new(TestHost::default()),
+    Arc::default(),
+    Arc::default(),
+  )
+}
+
+#[derive(Clone)]
+struct EchoServer;
+
+impl ServerHandler for EchoServer {
+  fn get_info(&self) -> ServerInfo {
+    ServerInfo {
+      capabilities: ServerCapabilities::builder()
+        .enable_tools()
+        .build(),
+      ..Default::default()
+    }
+  }
+
+  fn list_tools(
+    &self,
+    _request: Option<PaginatedRequestParam>,
+    _context: RequestContext<RoleServer>,
+  ) -> impl Future<Output = Result<ListToolsResult, ErrorData>> + Send + '_ {
+    std::future::ready(Ok(ListToolsResult::with_all_items(vec![Tool::new(
+      "echo",
+      "Echo the input back",
+      Arc::new(JsonObject::new()),
+    )])))
+  }
+}
+
+/// Reject requests missing the custom header or the bearer token.
+async fn authenticate(
+  request: Request,
+  next: Next,
+) -> Result<Response, StatusCode> {
+  let headers = request.headers();
+  let header = headers
+    .get(HEADER)
+    .and_then(|value| value.to_str().ok());
+  let authorization = headers
+    .get(AUTHORIZATION)
+    .and_then(|value| value.to_str().ok());
+  if header != Some(HEADER_VALUE) || authorization != Some(&format!("Bearer {TOKEN}")) {
+    return Err(StatusCode::UNAUTHORIZED);
+  }
+
+  Ok(next.run(request).await)
+}
+
+async fn listen() -> (TcpListener, SocketAddr) {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  (listener, addr)
+}
+
+fn serve(
+  listener: TcpListener,
+  router: Router,
+) {
+  tokio::spawn(async move {
+    axum::serve(listener, router.layer(middleware::from_fn(authenticate)))
+      .await
+      .unwrap();
+  });
+}
+
+async fn start_streamable_http_server() -> String {
+  let service = StreamableHttpService::new(
+    || Ok(EchoServer),
+    LocalSessionManager::default().into(),
+    Default::default(),
+  );
+  let (listener, addr) = listen().await;
+  serve(listener, Router::new().nest_service("/mcp", service));
+  format!("http://{addr}/mcp")
+}
+
+async fn start_sse_server() -> (String, CancellationToken) {
+  let (listener, addr) = listen().await;
+  let (server, router) = SseServer::new(SseServerConfig {
+    bind:           addr,
+    sse_path:       "/sse".to_string(),
+    post_path:      "/message".to_string(),
+    ct:             CancellationToken::new(),
+    sse_keep_alive: None,
+  });
+  let ct = server.with_service(|| EchoServer);
+  serve(listener, router);
+  (format!("http://{addr}/sse"), ct)
+}
+
+fn credentials() -> (HashMap<String, String>, Option<String>) {
+  (
+    HashMap::from([(HEADER.to_string(), HEADER_VALUE.to_string())]),
+    Some(TOKEN.to_string()),
+  )
+}
+
+async fn assert_lists_tools(transport: McpTransport) {
+  let client = transport.connect(handler()).await.unwrap();
+  let tools = client.list_all_tools().await.unwrap();
+  assert_eq!(
+    tools
+      .iter()
+      .map(|tool| tool.name.as_ref())
+      .collect::<Vec<_>>(),
+    ["echo"]
+  );
+  client.cancel().await.unwrap();
+}
+
+#[tokio::test]
+async fn lists_tools_over_streamable_http() {
+  let url = start_streamable_http_server().await;
+  let (headers, bearer_token) = credentials();
+
+  assert_lists_tools(McpTransport::StreamableHttp {
+    url,
+    headers,
+    bearer_token,
+  })
+  .await;
+}
+
+#[tokio::test]
+async fn lists_tools_over_sse() {
+  let (url, ct) = start_sse_server().await;
+  let (headers, bearer_token) = credentials();
+
+  assert_lists_tools(McpTransport::Sse {
+    url,
+    headers,
+    bearer_token,
+  })
+  .await;
+  ct.cancel();
+}
+
+#[tokio::test]
+async fn fails_to_connect_without_the_bearer_token() {
+  let url = start_streamable_http_server().await;
+  let (headers, _) = credentials();
+
+  let transport = McpTransport::StreamableHttp {
+    url,
+    headers,
+    bearer_token: None,
+  };
+  assert!(transport.connect(handler()).await.is_err());
+
+  let (url, ct) = start_sse_server().await;
+  let (headers, _) = credentials();
+  let transport = McpTransport::Sse {
+    url,
+    headers,
+    bearer_token: None,
+  };
+  assert!(transport.connect(handler()).await.is_err());
+  ct.cancel();
+}
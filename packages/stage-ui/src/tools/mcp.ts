@@ -1,24 +1,39 @@
-import { callTool, connectServer, disconnectServer, listTools } from '@proj-airi/tauri-plugin-mcp'
+import type { CallToolResult } from '@proj-airi/tauri-plugin-mcp'
+
+import { callTool, connectServer, disconnectServer, listServers, listTools } from '@proj-airi/tauri-plugin-mcp'
 import { tool } from '@xsai/tool'
 import { z } from 'zod'
 
+const serverId = z.string().describe('The id of the MCP server')
+
 const tools = [
   tool({
-    name: 'mcp_list_tools',
-    description: 'List all tools available on the MCP server',
+    name: 'mcp_list_servers',
+    description: 'List all MCP servers and their status',
     execute: async (_, __) => {
-      return await listTools()
+      return await listServers()
     },
     parameters: z.object({}),
   }),
+  tool({
+    name: 'mcp_list_tools',
+    description: 'List all tools available on the MCP server',
+    execute: async ({ serverId }) => {
+      return await listTools(serverId)
+    },
+    parameters: z.object({
+      serverId,
+    }),
+  }),
   tool({
     name: 'mcp_connect_server',
     description: 'Connect to the MCP server. If "success", the connection to the MCP server is successful. Otherwise, the connection fails.',
-    execute: async ({ command, args }) => {
-      await connectServer(command, args)
+    execute: async ({ serverId, command, args }) => {
+      await connectServer(serverId, { type: 'stdio', command, args })
       return 'success'
     },
     parameters: z.object({
+      serverId,
       command: z.string().describe('The command to connect to the MCP server'),
       args: z.array(z.string()).describe('The arguments to pass to the MCP server'),
     }),
@@ -26,27 +41,24 @@ const tools = [
   tool({
     name: 'mcp_disconnect_server',
     description: 'Disconnect from the MCP server. If "success", the disconnection from the MCP server is successful. Otherwise, the disconnection fails.',
-    execute: async () => {
-      await disconnectServer()
+    execute: async ({ serverId }) => {
+      await disconnectServer(serverId)
       return 'success'
     },
-    parameters: z.object({}),
+    parameters: z.object({
+      serverId,
+    }),
   }),
   tool({
     name: 'mcp_call_tool',
     description: 'Call a tool on the MCP server. The result is a list of content and a boolean indicating whether the tool call is an error.',
-    execute: async ({ name, parameters }) => {
+    execute: async ({ serverId, name, parameters }) => {
       const parametersObject = Object.fromEntries(parameters.map(({ name, value }) => [name, value]))
-      const result = await callTool(name, parametersObject)
-      return result satisfies {
-        content: {
-          type: string
-          text: string
-        }[]
-        isError: boolean
-      }
+      const result = await callTool(serverId, name, parametersObject)
+      return result satisfies CallToolResult
     },
     parameters: z.object({
+      serverId,
       name: z.string().describe('The name of the tool to call'),
       parameters: z.array(z.object({
         name: z.string().describe('The name of the parameter'),
@@ -1,4 +1,5 @@
-import { invoke } from '@tauri-apps/api/core'
+import { Channel, invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 
 export interface ToolInputSchema {
   required: string[]
@@ -11,12 +12,31 @@ export interface ToolInputSchema {
   }>
 }
 
+/**
+ * Image, audio or embedded binary resource of a tool result, written to the app cache dir.
+ * Loads with `convertFileSrc(path)` from `@tauri-apps/api/core`, served as the type detected from its bytes.
+ * Embedded text resources, and content detected as HTML, XML or a script, stay inline in the result.
+ */
+export interface AssetContent {
+  type: 'asset'
+  kind: 'image' | 'audio' | 'resource'
+  mimeType?: string
+  /** URI of an embedded resource */
+  uri?: string
+  path: string
+  /** Size of the decoded content in bytes */
+  size: number
+}
+
+export type ToolContent
+  = | { type: 'text', text: string }
+    | { type: 'resource_link', uri: string, name: string, mimeType?: string }
+    | AssetContent
+
 export interface CallToolResult {
-  content: {
-    type: string
-    text: string
-  }[]
-  isError: boolean
+  content: ToolContent[]
+  structuredContent?: unknown
+  isError?: boolean
 }
 
 export interface Tool {
@@ -25,18 +45,540 @@ export interface Tool {
   inputSchema: ToolInputSchema
 }
 
-export async function connectServer(command: string, args: string[]) {
-  await invoke('plugin:mcp|connect_server', { command, args })
+export interface ServerInfo {
+  protocolVersion: string
+  capabilities: Record<string, unknown>
+  serverInfo: {
+    name: string
+    version: string
+  }
+  instructions?: string
+}
+
+/** Limits of a stdio server, resource limits and process groups only take effect on Linux */
+export interface Sandbox {
+  /** `RLIMIT_AS` */
+  maxMemoryBytes?: number
+  /** `RLIMIT_CPU` */
+  maxCpuSeconds?: number
+  /** `RLIMIT_NOFILE` */
+  maxOpenFiles?: number
+  /** Run in an empty directory of the server's own when `cwd` is not set */
+  isolateCwd?: boolean
+  /** Variables of the app's environment passed on, all others are cleared */
+  envAllowlist?: string[]
+  /** Kill the server together with everything it spawned on disconnect */
+  processGroup?: boolean
+}
+
+export type Transport
+  = | { type: 'stdio', command: string, args?: string[], env?: Record<string, string>, cwd?: string, sandbox?: Sandbox }
+    | { type: 'streamable-http', url: string, headers?: Record<string, string>, bearerToken?: string }
+    | { type: 'sse', url: string, headers?: Record<string, string>, bearerToken?: string }
+
+/** Rejection value of every command */
+export type McpError
+  = | { kind: 'not-connected' | 'already-connected', message: string, serverId: string }
+    | { kind: 'not-ready', message: string, serverId: string, status: ServerStatus }
+    | { kind: 'rpc', message: string, code: number, data?: unknown }
+    | { kind: 'timeout', message: string, timeoutMs: number }
+    | { kind: 'invalid-arguments', message: string, tool: string, errors: ValidationError[] }
+    | { kind: 'invalid-transport' | 'spawn' | 'handshake' | 'transport-closed' | 'cancelled' | 'denied' | 'config' | 'secret' | 'other', message: string }
+
+export interface ValidationError {
+  /** JSON pointer to the offending argument, empty for the arguments object itself */
+  path: string
+  /** JSON pointer to the failed schema keyword */
+  schemaPath: string
+  message: string
+}
+
+export type ServerStatus = 'connecting' | 'ready' | 'crashed' | 'stopped'
+
+export interface ServerExit {
+  reason: string
+  /** Milliseconds since the Unix epoch */
+  timestamp: number
+  uptimeMs?: number
+}
+
+export interface ServerSummary {
+  id: string
+  status: ServerStatus
+  serverInfo?: ServerInfo
+  restarts: number
+  lastExit?: ServerExit
+}
+
+export async function connectServer(id: string, transport: Transport) {
+  await invoke('plugin:mcp|connect_server', { id, transport })
+}
+
+export async function disconnectServer(id: string) {
+  await invoke('plugin:mcp|disconnect_server', { id })
+}
+
+export async function listServers(): Promise<ServerSummary[]> {
+  return await invoke('plugin:mcp|list_servers')
+}
+
+export async function getServerStatus(id: string): Promise<ServerSummary> {
+  return await invoke('plugin:mcp|get_server_status', { id })
+}
+
+export async function onServerStatus(handler: (server: ServerSummary) => void) {
+  return await listen<ServerSummary>('tauri-plugins:tauri-plugin-mcp:server-status', event => handler(event.payload))
+}
+
+/** Emitted with the ids of the stopped servers once every server is down after the app asked the plugin to shut down */
+export async function onShutdown(handler: (serverIds: string[]) => void) {
+  return await listen<string[]>('tauri-plugins:tauri-plugin-mcp:shutdown', event => handler(event.payload))
+}
+
+export async function listTools(id: string): Promise<Tool[]> {
+  return await invoke('plugin:mcp|list_tools', { id })
+}
+
+export interface NamespacedTool extends Tool {
+  serverId: string
+  /** The name the server knows the tool by, `name` is `<serverId>__<toolName>` */
+  toolName: string
+}
+
+/** Tools of every ready server, named so they can't collide */
+export async function listAllTools(): Promise<NamespacedTool[]> {
+  return await invoke('plugin:mcp|list_all_tools')
+}
+
+/** The server sent `notifications/tools/list_changed`, the cached tools are already refreshed */
+export async function onToolListChanged(handler: (event: { serverId: string }) => void) {
+  return await listen<{ serverId: string }>('tauri-plugins:tauri-plugin-mcp:tool-list-changed', event => handler(event.payload))
+}
+
+export interface Progress {
+  progressToken: string | number
+  progress: number
+  total?: number
+  message?: string
+}
+
+export interface CallToolOptions {
+  /** Fail with a `timeout` error, and cancel the call on the server, after this long */
+  timeoutMs?: number
+  /** Chosen by the caller, pass it to `cancelToolCall` to abort the call */
+  requestId?: string
+  /** Convert arguments of the wrong primitive type, e.g. `"42"` for an integer, before validating them */
+  coerce?: boolean
+  onProgress?: (progress: Progress) => void
+}
+
+export async function callTool(id: string | undefined, name: string, args: Record<string, unknown>, options: CallToolOptions = {}): Promise<CallToolResult> {
+  const onProgress = new Channel<Progress>()
+  if (options.onProgress)
+    onProgress.onmessage = options.onProgress
+
+  return await invoke('plugin:mcp|call_tool', {
+    id,
+    name,
+    args,
+    coerce: options.coerce,
+    timeoutMs: options.timeoutMs,
+    requestId: options.requestId,
+    onProgress,
+  })
+}
+
+/** Call a tool by the namespaced name from `listAllTools` */
+export async function callNamespacedTool(name: string, args: Record<string, unknown>, options: CallToolOptions = {}): Promise<CallToolResult> {
+  return await callTool(undefined, name, args, options)
+}
+
+export async function cancelToolCall(requestId: string, reason?: string) {
+  await invoke('plugin:mcp|cancel_tool_call', { requestId, reason })
+}
+
+/** Names of the stored secrets, their values can't be read back */
+export async function listSecrets(): Promise<string[]> {
+  return await invoke('plugin:mcp|list_secrets')
+}
+
+/**
+ * Store a secret, referenced as `${secret:NAME}` in a server's command, args, env, URL or headers.
+ *
+ * Secrets are encrypted with a key stored next to them in the app data dir, not in the OS keychain,
+ * so anyone who can read that directory can decrypt them.
+ */
+export async function setSecret(name: string, value: string) {
+  await invoke('plugin:mcp|set_secret', { name, value })
+}
+
+export async function deleteSecret(name: string) {
+  await invoke('plugin:mcp|delete_secret', { name })
+}
+
+/** `auto` when a rule allowed the call, `user` when it was allowed through a consent request */
+export type Approval = 'auto' | 'user'
+
+export interface AuditEntry {
+  /** Milliseconds since the Unix epoch */
+  timestamp: number
+  serverId: string
+  tool: string
+  arguments?: Record<string, unknown>
+  /** Not set when the call was rejected before or by the policy */
+  approval?: Approval
+  durationMs: number
+  /** Summary of the returned content */
+  result?: string
+  error?: string
+}
+
+export interface AuditQuery {
+  /** Milliseconds since the Unix epoch */
+  from?: number
+  /** Milliseconds since the Unix epoch */
+  to?: number
+  serverId?: string
+  tool?: string
+  /** Only return the most recent entries */
+  limit?: number
+}
+
+export async function queryAuditLog(query: AuditQuery = {}): Promise<AuditEntry[]> {
+  return await invoke('plugin:mcp|query_audit_log', { ...query })
+}
+
+export type LogLevel = 'debug' | 'info' | 'notice' | 'warning' | 'error' | 'critical' | 'alert' | 'emergency'
+
+export interface LogEntry {
+  serverId: string
+  source: 'stderr' | 'notification'
+  level?: LogLevel
+  logger?: string
+  message: string
+  /** Milliseconds since the Unix epoch */
+  timestamp: number
+}
+
+export async function getServerLogs(id: string, limit?: number): Promise<LogEntry[]> {
+  return await invoke('plugin:mcp|get_server_logs', { id, limit })
+}
+
+export async function setLogLevel(id: string, level: LogLevel) {
+  await invoke('plugin:mcp|set_log_level', { id, level })
+}
+
+export async function onServerLog(handler: (entry: LogEntry) => void) {
+  return await listen<LogEntry>('tauri-plugins:tauri-plugin-mcp:server-log', event => handler(event.payload))
+}
+
+export interface Resource {
+  uri: string
+  name: string
+  title?: string
+  description?: string
+  mimeType?: string
+  size?: number
+}
+
+export interface ResourceTemplate {
+  uriTemplate: string
+  name: string
+  title?: string
+  description?: string
+  mimeType?: string
+}
+
+export type ResourceContents
+  = | { uri: string, mimeType?: string, text: string }
+    | { uri: string, mimeType?: string, blob: string }
+
+export interface ListResourcesResult {
+  resources: Resource[]
+  nextCursor?: string
+}
+
+export interface ListResourceTemplatesResult {
+  resourceTemplates: ResourceTemplate[]
+  nextCursor?: string
+}
+
+export interface ReadResourceResult {
+  contents: ResourceContents[]
+}
+
+export interface ResourceUpdatedEvent {
+  serverId: string
+  uri: string
+}
+
+export async function listResources(id: string, cursor?: string): Promise<ListResourcesResult> {
+  return await invoke('plugin:mcp|list_resources', { id, cursor })
+}
+
+export async function listResourceTemplates(id: string, cursor?: string): Promise<ListResourceTemplatesResult> {
+  return await invoke('plugin:mcp|list_resource_templates', { id, cursor })
 }
 
-export async function disconnectServer() {
-  await invoke('plugin:mcp|disconnect_server')
+export async function readResource(id: string, uri: string): Promise<ReadResourceResult> {
+  return await invoke('plugin:mcp|read_resource', { id, uri })
 }
 
-export async function listTools(): Promise<Tool[]> {
-  return await invoke('plugin:mcp|list_tools')
+export async function subscribeResource(id: string, uri: string) {
+  await invoke('plugin:mcp|subscribe_resource', { id, uri })
+}
+
+export async function unsubscribeResource(id: string, uri: string) {
+  await invoke('plugin:mcp|unsubscribe_resource', { id, uri })
+}
+
+export async function onResourceUpdated(handler: (event: ResourceUpdatedEvent) => void) {
+  return await listen<ResourceUpdatedEvent>('tauri-plugins:tauri-plugin-mcp:resource-updated', event => handler(event.payload))
+}
+
+export async function onResourceListChanged(handler: (event: { serverId: string }) => void) {
+  return await listen<{ serverId: string }>('tauri-plugins:tauri-plugin-mcp:resource-list-changed', event => handler(event.payload))
+}
+
+export interface PromptArgument {
+  name: string
+  title?: string
+  description?: string
+  required?: boolean
+}
+
+export interface Prompt {
+  name: string
+  title?: string
+  description?: string
+  arguments?: PromptArgument[]
+}
+
+export interface PromptMessage {
+  role: 'user' | 'assistant'
+  content:
+    | { type: 'text', text: string }
+    | { type: 'image', data: string, mimeType: string }
+    | { type: 'resource', resource: ResourceContents }
+    | { type: 'resource_link', uri: string, name: string }
+}
+
+export interface ListPromptsResult {
+  prompts: Prompt[]
+  nextCursor?: string
+}
+
+export interface GetPromptResult {
+  description?: string
+  messages: PromptMessage[]
+}
+
+export type CompletionReference
+  = | { type: 'ref/prompt', name: string }
+    | { type: 'ref/resource', uri: string }
+
+export interface CompleteResult {
+  completion: {
+    values: string[]
+    total?: number
+    hasMore?: boolean
+  }
+}
+
+export async function listPrompts(id: string, cursor?: string): Promise<ListPromptsResult> {
+  return await invoke('plugin:mcp|list_prompts', { id, cursor })
+}
+
+export async function getPrompt(id: string, name: string, args?: Record<string, string>): Promise<GetPromptResult> {
+  return await invoke('plugin:mcp|get_prompt', { id, name, args })
+}
+
+export async function complete(
+  id: string,
+  reference: CompletionReference,
+  argument: { name: string, value: string },
+  context?: { arguments?: Record<string, string> },
+): Promise<CompleteResult> {
+  return await invoke('plugin:mcp|complete', { id, reference, argument, context })
+}
+
+export async function onPromptListChanged(handler: (event: { serverId: string }) => void) {
+  return await listen<{ serverId: string }>('tauri-plugins:tauri-plugin-mcp:prompt-list-changed', event => handler(event.payload))
+}
+
+export interface ServerConfig {
+  /** Inferred from `command` / `url` when omitted */
+  transport?: 'stdio' | 'streamable-http' | 'sse'
+  command?: string
+  args?: string[]
+  env?: Record<string, string>
+  cwd?: string
+  sandbox?: Sandbox
+  url?: string
+  headers?: Record<string, string>
+  bearerToken?: string
+  disabled?: boolean
+}
+
+export interface Root {
+  /** Absolute path of the directory */
+  path: string
+  name?: string
+}
+
+export interface Config {
+  mcpServers: Record<string, ServerConfig>
+  /** Directories servers may work in, served through `roots/list` */
+  roots?: Root[]
+}
+
+export async function getConfig(): Promise<Config> {
+  return await invoke('plugin:mcp|get_config')
+}
+
+/** Resolves to the entries that could not be started, keyed by server id */
+export async function setConfig(config: Config): Promise<Record<string, McpError>> {
+  return await invoke('plugin:mcp|set_config', { config })
+}
+
+export async function reloadConfig(): Promise<Config> {
+  return await invoke('plugin:mcp|reload_config')
+}
+
+export async function onConfigChanged(handler: (config: Config) => void) {
+  return await listen<Config>('tauri-plugins:tauri-plugin-mcp:config-changed', event => handler(event.payload))
+}
+
+/** Entries of the applied config that could not be started, keyed by server id, empty when all of them were */
+export async function onConfigErrors(handler: (errors: Record<string, McpError>) => void) {
+  return await listen<Record<string, McpError>>('tauri-plugins:tauri-plugin-mcp:config-errors', event => handler(event.payload))
+}
+
+export async function getRoots(): Promise<Root[]> {
+  return await invoke('plugin:mcp|get_roots')
+}
+
+/** Replace the roots and notify the connected servers */
+export async function setRoots(roots: Root[]) {
+  await invoke('plugin:mcp|set_roots', { roots })
+}
+
+export type ToolPermission = 'allow' | 'ask' | 'deny'
+
+export interface ServerPolicy {
+  default?: ToolPermission
+  /** Keyed by tool name */
+  tools?: Record<string, ToolPermission>
+  sampling?: ToolPermission
+}
+
+export interface Policy {
+  default: ToolPermission
+  /** Defaults to `ask` */
+  sampling?: ToolPermission
+  servers: Record<string, ServerPolicy>
+}
+
+export interface ConsentRequest {
+  consentId: string
+  kind: 'tool-call' | 'sampling'
+  serverId: string
+  /** Only set for tool calls */
+  tool?: string
+  /** Tool call arguments, or the parameters of the sampling request */
+  arguments?: Record<string, unknown>
+  definition?: Tool & { annotations?: { title?: string, readOnlyHint?: boolean, destructiveHint?: boolean } }
+}
+
+export async function getPolicy(): Promise<Policy> {
+  return await invoke('plugin:mcp|get_policy')
+}
+
+export async function setPolicy(policy: Policy) {
+  await invoke('plugin:mcp|set_policy', { policy })
+}
+
+/** Answer a consent request, `remember` stores the answer as the tool's rule */
+export async function respondConsent(consentId: string, approved: boolean, remember = false) {
+  await invoke('plugin:mcp|respond_consent', { consentId, approved, remember })
+}
+
+export async function onConsentRequest(handler: (request: ConsentRequest) => void) {
+  return await listen<ConsentRequest>('tauri-plugins:tauri-plugin-mcp:consent-request', event => handler(event.payload))
+}
+
+export type SamplingContent
+  = | { type: 'text', text: string }
+    | { type: 'image', data: string, mimeType: string }
+    | { type: 'audio', data: string, mimeType: string }
+
+export interface SamplingMessage {
+  role: 'user' | 'assistant'
+  content: SamplingContent
+}
+
+export interface SamplingRequest {
+  samplingId: string
+  serverId: string
+  messages: SamplingMessage[]
+  modelPreferences?: {
+    hints?: { name?: string }[]
+    costPriority?: number
+    speedPriority?: number
+    intelligencePriority?: number
+  }
+  systemPrompt?: string
+  includeContext?: 'none' | 'thisServer' | 'allServers'
+  temperature?: number
+  maxTokens: number
+  stopSequences?: string[]
+  metadata?: unknown
+}
+
+export interface SamplingResult extends SamplingMessage {
+  model: string
+  stopReason?: 'endTurn' | 'stopSequence' | 'maxTokens' | string
+}
+
+export async function respondSampling(samplingId: string, result: SamplingResult) {
+  await invoke('plugin:mcp|respond_sampling', { samplingId, result })
+}
+
+export async function rejectSampling(samplingId: string, message?: string) {
+  await invoke('plugin:mcp|reject_sampling', { samplingId, message })
+}
+
+/** A server asks to run a completion with the user's LLM */
+export async function onSamplingRequest(handler: (request: SamplingRequest) => void) {
+  return await listen<SamplingRequest>('tauri-plugins:tauri-plugin-mcp:sampling-request', event => handler(event.payload))
+}
+
+export type ElicitationProperty
+  = | { type: 'string', title?: string, description?: string, enum?: string[], enumNames?: string[], format?: 'email' | 'uri' | 'date' | 'date-time', minLength?: number, maxLength?: number, default?: string }
+    | { type: 'number' | 'integer', title?: string, description?: string, minimum?: number, maximum?: number, default?: number }
+    | { type: 'boolean', title?: string, description?: string, default?: boolean }
+
+export interface ElicitationSchema {
+  type: 'object'
+  properties: Record<string, ElicitationProperty>
+  required?: string[]
+}
+
+export interface ElicitationRequest {
+  elicitationId: string
+  serverId: string
+  message: string
+  requestedSchema: ElicitationSchema
+}
+
+export type ElicitationAction = 'accept' | 'decline' | 'cancel'
+
+/** Answer an elicitation request, `content` is only sent when accepted */
+export async function respondElicitation(elicitationId: string, action: ElicitationAction, content?: Record<string, unknown>) {
+  await invoke('plugin:mcp|respond_elicitation', { elicitationId, action, content })
 }
 
-export async function callTool(name: string, args: Record<string, unknown>): Promise<CallToolResult> {
-  return await invoke('plugin:mcp|call_tool', { name, args })
+/** A server asks the user to fill in a form */
+export async function onElicitationRequest(handler: (request: ElicitationRequest) => void) {
+  return await listen<ElicitationRequest>('tauri-plugins:tauri-plugin-mcp:elicitation-request', event => handler(event.payload))
 }